@@ -0,0 +1,352 @@
+//! An in-process handle to control a running mock server.
+//!
+//! # MockHandle
+//!
+//! A [`MockHandle`] shares the [`World`] with a [`MockServer`], you can get one by [`MockServer::handle`].
+//!
+//! ```ignore
+//! let server = MockServer::new("localhost", 8080);
+//! let handle = server.handle();
+//!
+//! // start the server in another task, then:
+//! handle.group_message(1, 100, 11, vec!["/roll".into()])?;
+//!
+//! // after the bot handled the message:
+//! let replies = handle.sent_messages();
+//! ```
+//!
+//! Events are pushed to every session which is bound with the receiving bot, just like mirai-api-http does.
+//!
+//! [`MockServer`]: crate::mock::MockServer
+//! [`MockServer::handle`]: crate::mock::MockServer::handle
+
+use std::sync::{Mutex, MutexGuard};
+
+use actix_web::web;
+use serde_json::{json, Value};
+
+use mirai::Target;
+use mirai::error::NO_SUCH_TARGET;
+use mirai::message::{MessageChain, MessageID};
+
+use crate::world::{now, Group, Member, Permission, RecordedRequest, SentMessage, World, WorldResult};
+
+#[derive(Clone)]
+pub struct MockHandle {
+    world: web::Data<Mutex<World>>,
+}
+
+impl MockHandle {
+    pub(crate) fn new(world: web::Data<Mutex<World>>) -> MockHandle {
+        MockHandle { world }
+    }
+
+    /// Lock the world, the server can not answer any request until the guard is dropped.
+    pub fn world(&self) -> MutexGuard<'_, World> {
+        self.world.lock().unwrap()
+    }
+
+    /// Push a raw event to the sessions which are bound with the given bot.
+    pub fn push_event(&self, bot: Target, event: Value) {
+        self.world().push_event(bot, event);
+    }
+
+    fn message_chain(id: MessageID, message_chain: MessageChain) -> Value {
+        let mut chain = vec![json! {{
+            "type": "Source",
+            "id": id,
+            "time": now(),
+        }}];
+
+        chain.extend(message_chain.iter().map(|single| serde_json::to_value(single).unwrap()));
+
+        Value::Array(chain)
+    }
+
+    /// Let the [sender] say something in the [group], and return the id of the message.
+    pub fn group_message(&self, bot: Target, group: Target, sender: Target, message_chain: MessageChain) -> WorldResult<MessageID> {
+        let mut world = self.world();
+
+        let sender = {
+            let group = world.bots.get(&bot).ok_or(NO_SUCH_TARGET)?.group(group)?;
+            group.member_json(group.member(sender)?)
+        };
+
+        let id = world.next_message_id();
+
+        world.push_event(bot, json! {{
+            "type": "GroupMessage",
+            "messageChain": MockHandle::message_chain(id, message_chain),
+            "sender": sender,
+        }});
+
+        Ok(id)
+    }
+
+    /// Let the [friend] say something to the bot, and return the id of the message.
+    pub fn friend_message(&self, bot: Target, friend: Target, message_chain: MessageChain) -> WorldResult<MessageID> {
+        let mut world = self.world();
+
+        let sender = {
+            let friend = world.bots.get(&bot).ok_or(NO_SUCH_TARGET)?.friend(friend)?;
+
+            json! {{
+                "id": friend.id,
+                "nickname": friend.nickname,
+                "remark": friend.remark,
+            }}
+        };
+
+        let id = world.next_message_id();
+
+        world.push_event(bot, json! {{
+            "type": "FriendMessage",
+            "messageChain": MockHandle::message_chain(id, message_chain),
+            "sender": sender,
+        }});
+
+        Ok(id)
+    }
+
+    /// Recall a group message which was sent by [author], the [operator] is the bot itself if it is `None`.
+    pub fn group_recall(&self, bot: Target, group: Target, author: Target, message_id: MessageID, operator: Option<Target>) -> WorldResult<()> {
+        let mut world = self.world();
+
+        let (group, operator) = {
+            let group = world.bots.get(&bot).ok_or(NO_SUCH_TARGET)?.group(group)?;
+            let operator = match operator {
+                Some(operator) => group.member_json(group.member(operator)?),
+                None => Value::Null,
+            };
+
+            (group.to_json(), operator)
+        };
+
+        world.push_event(bot, json! {{
+            "type": "GroupRecallEvent",
+            "authorId": author,
+            "messageId": message_id,
+            "time": now(),
+            "group": group,
+            "operator": operator,
+        }});
+
+        Ok(())
+    }
+
+    pub fn friend_recall(&self, bot: Target, friend: Target, message_id: MessageID) -> WorldResult<()> {
+        let mut world = self.world();
+
+        world.bots.get(&bot).ok_or(NO_SUCH_TARGET)?.friend(friend)?;
+        world.push_event(bot, json! {{
+            "type": "FriendRecallEvent",
+            "authorId": friend,
+            "messageId": message_id,
+            "time": now(),
+            "operator": friend,
+        }});
+
+        Ok(())
+    }
+
+    /// Apply [f] to the member, and push the event which is built by [event] with the result of [f] and the member (after applying).
+    fn member_event<R, F, E>(&self, bot: Target, group: Target, member: Target, f: F, event: E) -> WorldResult<()> where
+        F: FnOnce(&mut Member) -> R,
+        E: FnOnce(R, Value) -> Value {
+        let mut world = self.world();
+
+        let event = {
+            let group = world.bots.get_mut(&bot).ok_or(NO_SUCH_TARGET)?.group_mut(group)?;
+            let result = f(group.member_mut(member)?);
+
+            event(result, group.member_json(group.member(member)?))
+        };
+
+        world.push_event(bot, event);
+
+        Ok(())
+    }
+
+    /// A new member joins the [group].
+    pub fn member_join(&self, bot: Target, group: Target, member: Member) -> WorldResult<()> {
+        let id = member.id;
+
+        self.world().bots.get_mut(&bot).ok_or(NO_SUCH_TARGET)?
+            .group_mut(group)?
+            .members.insert(id, member);
+
+        self.member_event(bot, group, id, |_| (), |_, member| json! {{
+            "type": "MemberJoinEvent",
+            "member": member,
+        }})
+    }
+
+    /// The [member] leaves the [group], it is kicked by the [operator] if the [operator] is not `None`.
+    pub fn member_leave(&self, bot: Target, group: Target, member: Target, operator: Option<Target>) -> WorldResult<()> {
+        let mut world = self.world();
+
+        let event = {
+            let group = world.bots.get_mut(&bot).ok_or(NO_SUCH_TARGET)?.group_mut(group)?;
+            let operator = match operator {
+                Some(operator) => Some(group.member_json(group.member(operator)?)),
+                None => None,
+            };
+
+            let member = group.members.remove(&member).ok_or(NO_SUCH_TARGET)?;
+            let member = group.member_json(&member);
+
+            match operator {
+                Some(operator) => json! {{
+                    "type": "MemberLeaveEventKick",
+                    "member": member,
+                    "operator": operator,
+                }},
+
+                None => json! {{
+                    "type": "MemberLeaveEventQuit",
+                    "member": member,
+                }},
+            }
+        };
+
+        world.push_event(bot, event);
+
+        Ok(())
+    }
+
+    /// The name card of the [member] is changed by itself.
+    pub fn member_card_change<S: AsRef<str>>(&self, bot: Target, group: Target, member: Target, current: S) -> WorldResult<()> {
+        let current = current.as_ref().to_string();
+
+        self.member_event(bot, group, member, |member| {
+            std::mem::replace(&mut member.name, current.clone())
+        }, |origin, member| json! {{
+            "type": "MemberCardChangeEvent",
+            "origin": origin,
+            "current": current,
+            "member": member,
+            "operator": member,
+        }})
+    }
+
+    /// The permission of the [member] is changed by the owner.
+    pub fn member_permission_change(&self, bot: Target, group: Target, member: Target, current: Permission) -> WorldResult<()> {
+        self.member_event(bot, group, member, |member| {
+            std::mem::replace(&mut member.permission, current)
+        }, |origin, member| json! {{
+            "type": "MemberPermissionChangeEvent",
+            "origin": origin,
+            "current": current,
+            "member": member,
+        }})
+    }
+
+    /// The [member] is muted for [seconds] by the bot, or unmuted if [seconds] is 0.
+    pub fn member_mute(&self, bot: Target, group: Target, member: Target, seconds: u32) -> WorldResult<()> {
+        self.member_event(bot, group, member, |member| {
+            member.mute_time_remaining = seconds;
+        }, |_, member| if seconds == 0 {
+            json! {{
+                "type": "MemberUnmuteEvent",
+                "member": member,
+                "operator": null,
+            }}
+        } else {
+            json! {{
+                "type": "MemberMuteEvent",
+                "durationSeconds": seconds,
+                "member": member,
+                "operator": null,
+            }}
+        })
+    }
+
+    /// The bot joins the [group].
+    pub fn bot_join_group(&self, bot: Target, group: Group) -> WorldResult<()> {
+        let mut world = self.world();
+
+        let event = json! {{
+            "type": "BotJoinGroupEvent",
+            "group": group.to_json(),
+        }};
+
+        world.bots.get_mut(&bot).ok_or(NO_SUCH_TARGET)?
+            .groups.insert(group.id, group);
+        world.push_event(bot, event);
+
+        Ok(())
+    }
+
+    /// The bot leaves the [group], it is kicked if [kicked] is `true`.
+    pub fn bot_leave_group(&self, bot: Target, group: Target, kicked: bool) -> WorldResult<()> {
+        let mut world = self.world();
+
+        let group = world.bots.get_mut(&bot).ok_or(NO_SUCH_TARGET)?
+            .groups.remove(&group).ok_or(NO_SUCH_TARGET)?;
+
+        world.push_event(bot, json! {{
+            "type": if kicked { "BotLeaveEventKick" } else { "BotLeaveEventActive" },
+            "group": group.to_json(),
+        }});
+
+        Ok(())
+    }
+
+    /// The group is disbanded by its owner, the bot leaves it as mirai-api-http 2.x reports.
+    pub fn bot_group_disband(&self, bot: Target, group: Target) -> WorldResult<()> {
+        let mut world = self.world();
+
+        let group = world.bots.get_mut(&bot).ok_or(NO_SUCH_TARGET)?
+            .groups.remove(&group).ok_or(NO_SUCH_TARGET)?;
+
+        world.push_event(bot, json! {{
+            "type": "BotLeaveEventDisband",
+            "group": group.to_json(),
+            "operator": null,
+        }});
+
+        Ok(())
+    }
+
+    /// The permission of the bot in the [group] is changed by the owner.
+    pub fn bot_permission_change(&self, bot: Target, group: Target, current: Permission) -> WorldResult<()> {
+        let mut world = self.world();
+
+        let event = {
+            let group = world.bots.get_mut(&bot).ok_or(NO_SUCH_TARGET)?.group_mut(group)?;
+            let origin = std::mem::replace(&mut group.permission, current);
+
+            json! {{
+                "type": "BotGroupPermissionChangeEvent",
+                "origin": origin,
+                "current": current,
+                "group": group.to_json(),
+            }}
+        };
+
+        world.push_event(bot, event);
+
+        Ok(())
+    }
+
+    /// Return all the messages which were sent by the client, in the order of sending.
+    pub fn sent_messages(&self) -> Vec<SentMessage> {
+        let mut messages: Vec<SentMessage> = self.world().sent.values().cloned().collect();
+        messages.sort_by_key(|message| message.id);
+
+        messages
+    }
+
+    /// Return all the requests which were sent by the client, in the order of sending.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.world().requests.clone()
+    }
+
+    /// Forget all the recorded messages and requests.
+    pub fn clear_records(&self) {
+        let mut world = self.world();
+
+        world.sent.clear();
+        world.requests.clear();
+    }
+}
@@ -0,0 +1,5 @@
+//! A mock of mirai-api-http server, it runs on an in-memory [`world::World`] so bots can be tested offline.
+
+pub mod handle;
+pub mod mock;
+pub mod world;
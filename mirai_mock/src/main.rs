@@ -1,9 +1,7 @@
-mod mock;
-
-use mock::MockServer;
+use mirai_rs_mock::mock::MockServer;
 
 #[actix_rt::main]
 async fn main() -> std::io::Result<()> {
     println!("Running...");
-    MockServer::new(mirai_test::HOST, mirai_test::PORT).start().await
-}
\ No newline at end of file
+    MockServer::new("localhost", 8080).start().await
+}
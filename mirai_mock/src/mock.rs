@@ -1,14 +1,812 @@
-use actix_web::{get, App, HttpServer, Responder, HttpRequest};
-use mirai_test::data::session::About;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::ops::Deref;
+use std::sync::{mpsc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use actix_multipart::Multipart;
+use actix_web::{get, post, web, App, HttpServer, HttpResponse, Responder, HttpRequest, FromRequest};
+use actix_web::dev::{Payload, Server};
+use actix_web::error::{Error, ErrorBadRequest};
+use actix_web::http::Method;
+use futures::StreamExt;
+use futures::future::{FutureExt, LocalBoxFuture};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+use mirai::{Code, Target};
+use mirai::error::{
+    SUCCESS, WRONG_AUTH_KEY, NO_SUCH_BOT, WRONG_SESSION, UNAUTHORIZED, NO_SUCH_TARGET,
+    NO_SUCH_FILE, PERMISSION_DENIED, MUTED, MESSAGE_TOO_LONG, BAD_REQUEST,
+};
+use mirai::message::{MessageChain, MessageChannel, MessageID};
+use mirai::message::event::NudgeKind;
+use mirai::session::{AboutData, AboutResponse};
+
+use crate::handle::MockHandle;
+use crate::world::{now, Announcement, World, WorldResult, Permission, SentMessage};
+
+/// The version which the mock server reports in `/about`.
+pub const VERSION: &str = "mirai_rs_mock";
+
+/// The max length of the text in a message, longer messages are rejected with [`MESSAGE_TOO_LONG`].
+const MAX_MESSAGE_LENGTH: usize = 4500;
+
+/// The max mute time in seconds (30 days), which is the same as QQ.
+const MAX_MUTE_TIME: u32 = 30 * 24 * 60 * 60;
+
+pub(crate) type WorldData = web::Data<Mutex<World>>;
 
 pub struct MockServer {
     host: String,
     port: u16,
+    world: WorldData,
+}
+
+/// An extractor which records the request into the world before deserializing it.
+/// The query of a GET request is recorded as its body.
+struct Recorded<T>(T);
+
+impl<T> Deref for Recorded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T: DeserializeOwned + 'static> FromRequest for Recorded<T> {
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+    type Config = ();
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let method = req.method().to_string();
+        let path = req.path().to_string();
+        let world = req.app_data::<WorldData>().cloned();
+
+        let record = move |body: Value| {
+            if let Some(world) = world {
+                world.lock().unwrap().record_request(&method, &path, body);
+            }
+        };
+
+        if req.method() == Method::GET {
+            let result = web::Query::<HashMap<String, String>>::from_query(req.query_string())
+                .and_then(|query| {
+                    record(json!(query.into_inner()));
+                    web::Query::<T>::from_query(req.query_string())
+                })
+                .map(|query| Recorded(query.into_inner()))
+                .map_err(Error::from);
+
+            futures::future::ready(result).boxed_local()
+        } else {
+            web::Json::<Value>::from_request(req, payload).map(|body| {
+                let body = body?.into_inner();
+                record(body.clone());
+
+                serde_json::from_value(body)
+                    .map(Recorded)
+                    .map_err(ErrorBadRequest)
+            }).boxed_local()
+        }
+    }
+}
+
+/// The messages which mirai-api-http answers with.
+fn describe(code: Code) -> &'static str {
+    match code {
+        SUCCESS => "success",
+        WRONG_AUTH_KEY => "Auth Key错误",
+        NO_SUCH_BOT => "指定Bot不存在",
+        WRONG_SESSION => "Session失效或不存在",
+        UNAUTHORIZED => "Session未认证(未激活)",
+        NO_SUCH_TARGET => "发送消息目标不存在(指定对象不存在)",
+        NO_SUCH_FILE => "指定文件不存在，出现于发送本地图片",
+        PERMISSION_DENIED => "无操作权限，指Bot没有对应操作的限权",
+        MUTED => "Bot被禁言，指Bot当前无法向指定群发送消息",
+        MESSAGE_TOO_LONG => "消息过长",
+        _ => "错误的访问，如参数错误等",
+    }
+}
+
+/// Answer with `{ "code": 0, "msg": "success" }` and the fields of the given object,
+/// or with `{ "code": code, "msg": .. }` if failed.
+fn respond(result: WorldResult<Value>) -> HttpResponse {
+    let body = match result {
+        Ok(Value::Object(mut fields)) => {
+            fields.insert("code".to_string(), json!(SUCCESS));
+            fields.insert("msg".to_string(), json!(describe(SUCCESS)));
+
+            Value::Object(fields)
+        }
+
+        Ok(_) => json! {{ "code": SUCCESS, "msg": describe(SUCCESS) }},
+        Err(code) => json! {{ "code": code, "msg": describe(code) }},
+    };
+
+    HttpResponse::Ok().json(body)
+}
+
+fn respond_common(result: WorldResult<()>) -> HttpResponse {
+    respond(result.map(|_| Value::Null))
+}
+
+/// Answer with the given value directly, just like the GET endpoints of mirai-api-http.
+fn respond_raw(result: WorldResult<Value>) -> HttpResponse {
+    match result {
+        Ok(value) => HttpResponse::Ok().json(value),
+        Err(code) => respond(Err(code)),
+    }
+}
+
+#[serde(rename_all = "camelCase")]
+#[derive(Deserialize)]
+struct SessionQuery {
+    session_key: String,
+    target: Option<Target>,
+    member_id: Option<Target>,
+    count: Option<usize>,
+}
+
+#[serde(rename_all = "camelCase")]
+#[derive(Deserialize)]
+struct AuthRequest {
+    auth_key: String,
+}
+
+#[serde(rename_all = "camelCase")]
+#[derive(Deserialize)]
+struct BindRequest {
+    session_key: String,
+    qq: Target,
+}
+
+#[serde(rename_all = "camelCase")]
+#[derive(Deserialize)]
+struct SendRequest {
+    session_key: String,
+    target: Option<Target>,
+    qq: Option<Target>,
+    group: Option<Target>,
+    quote: Option<MessageID>,
+    message_chain: Vec<Value>,
+}
+
+#[serde(rename_all = "camelCase")]
+#[derive(Deserialize)]
+struct TargetRequest {
+    session_key: String,
+    target: Target,
+}
+
+#[serde(rename_all = "camelCase")]
+#[derive(Deserialize)]
+struct MessageRequest {
+    session_key: String,
+    target: MessageID,
+}
+
+#[serde(rename_all = "camelCase")]
+#[derive(Deserialize)]
+struct MemberAdminRequest {
+    session_key: String,
+    target: Target,
+    member_id: Target,
+    assign: bool,
+}
+
+#[serde(rename_all = "camelCase")]
+#[derive(Deserialize)]
+struct AnnouncementQuery {
+    session_key: String,
+    id: Target,
+    offset: Option<usize>,
+    size: Option<usize>,
+}
+
+#[serde(rename_all = "camelCase")]
+#[derive(Deserialize)]
+struct PublishAnnouncementRequest {
+    session_key: String,
+    target: Target,
+    content: String,
+    #[serde(default)]
+    send_to_new_member: bool,
+    #[serde(default)]
+    pinned: bool,
+    #[serde(default)]
+    show_popup: bool,
+}
+
+#[serde(rename_all = "camelCase")]
+#[derive(Deserialize)]
+struct DeleteAnnouncementRequest {
+    session_key: String,
+    id: Target,
+    fid: String,
+}
+
+#[serde(rename_all = "camelCase")]
+#[derive(Deserialize)]
+struct MemberRequest {
+    session_key: String,
+    target: Target,
+    member_id: Target,
+    time: Option<u32>,
+}
+
+#[serde(rename_all = "camelCase")]
+#[derive(Deserialize)]
+struct GroupConfigRequest {
+    session_key: String,
+    target: Target,
+    config: Value,
+}
+
+#[serde(rename_all = "camelCase")]
+#[derive(Deserialize)]
+struct MemberInfoRequest {
+    session_key: String,
+    target: Target,
+    member_id: Target,
+    info: MemberInfoPatch,
+}
+
+#[serde(rename_all = "camelCase")]
+#[derive(Deserialize)]
+struct MemberInfoPatch {
+    name: Option<String>,
+    special_title: Option<String>,
+}
+
+#[serde(rename_all = "camelCase")]
+#[derive(Deserialize)]
+struct ConfigRequest {
+    session_key: String,
+    cache_size: Option<u16>,
+    enable_websocket: Option<bool>,
+}
+
+#[serde(rename_all = "camelCase")]
+#[derive(Deserialize)]
+struct NudgeRequest {
+    session_key: String,
+    target: Target,
+    subject: Target,
+    kind: NudgeKind,
 }
 
 #[get("/about")]
 async fn about(_req: HttpRequest) -> impl Responder {
-    serde_json::to_string(&About::response()).unwrap()
+    serde_json::to_string(&AboutResponse::new(0, AboutData::new(VERSION.to_string()))).unwrap()
+}
+
+#[post("/auth")]
+async fn auth(world: WorldData, req: Recorded<AuthRequest>) -> HttpResponse {
+    let result = world.lock().unwrap().auth(&req.auth_key);
+
+    respond(result.map(|session| json! {{ "session": session }}))
+}
+
+#[post("/verify")]
+async fn verify(world: WorldData, req: Recorded<BindRequest>) -> HttpResponse {
+    respond_common(world.lock().unwrap().verify(&req.session_key, req.qq))
+}
+
+#[post("/release")]
+async fn release(world: WorldData, req: Recorded<BindRequest>) -> HttpResponse {
+    respond_common(world.lock().unwrap().release(&req.session_key, req.qq))
+}
+
+fn check_message(chain: &[Value]) -> WorldResult<()> {
+    let length: usize = chain.iter()
+        .filter_map(|single| single.get("text").and_then(Value::as_str))
+        .map(|text| text.chars().count())
+        .sum();
+
+    if chain.is_empty() {
+        Err(BAD_REQUEST)
+    } else if length > MAX_MESSAGE_LENGTH {
+        Err(MESSAGE_TOO_LONG)
+    } else {
+        Ok(())
+    }
+}
+
+fn send_message(world: &mut World, req: &SendRequest, message_type: &str) -> WorldResult<MessageID> {
+    let bot = world.bound_bot(&req.session_key)?;
+
+    let channel = match message_type {
+        "Friend" => {
+            let friend = bot.friend(req.target.or(req.qq).ok_or(BAD_REQUEST)?)?;
+
+            MessageChannel::Friend(friend.id)
+        }
+
+        "Group" => {
+            let group = bot.group(req.target.or(req.group).ok_or(BAD_REQUEST)?)?;
+
+            if group.bot_muted || (group.mute_all && group.permission == Permission::Member) {
+                return Err(MUTED);
+            }
+
+            MessageChannel::Group(group.id)
+        }
+
+        _ => {
+            let group = bot.group(req.group.ok_or(BAD_REQUEST)?)?;
+            let member = group.member(req.qq.ok_or(BAD_REQUEST)?)?;
+
+            MessageChannel::Temp { qq: member.id, group: group.id }
+        }
+    };
+
+    check_message(&req.message_chain)?;
+
+    let bot = bot.id;
+    let message_chain: MessageChain = serde_json::from_value(Value::Array(req.message_chain.clone()))
+        .map_err(|_| BAD_REQUEST)?;
+
+    Ok(world.record_message(bot, channel, message_chain, req.quote))
+}
+
+async fn do_send_message(world: WorldData, req: Recorded<SendRequest>, message_type: &str) -> HttpResponse {
+    let result = send_message(&mut world.lock().unwrap(), &req, message_type);
+
+    respond(result.map(|id| json! {{ "messageId": id }}))
+}
+
+#[post("/sendFriendMessage")]
+async fn send_friend_message(world: WorldData, req: Recorded<SendRequest>) -> HttpResponse {
+    do_send_message(world, req, "Friend").await
+}
+
+#[post("/sendGroupMessage")]
+async fn send_group_message(world: WorldData, req: Recorded<SendRequest>) -> HttpResponse {
+    do_send_message(world, req, "Group").await
+}
+
+#[post("/sendTempMessage")]
+async fn send_temp_message(world: WorldData, req: Recorded<SendRequest>) -> HttpResponse {
+    do_send_message(world, req, "Temp").await
+}
+
+#[post("/sendNudge")]
+async fn send_nudge(world: WorldData, req: Recorded<NudgeRequest>) -> HttpResponse {
+    let world = world.lock().unwrap();
+
+    let result = world.bound_bot(&req.session_key).and_then(|bot| {
+        match req.kind {
+            NudgeKind::Friend => bot.friend(req.target).map(|_| ()),
+            NudgeKind::Group => bot.group(req.subject)?.member(req.target).map(|_| ()),
+            NudgeKind::Stranger => Ok(()),
+        }
+    });
+
+    respond_common(result)
+}
+
+/// Take (or peek if not [is_fetch]) at most `count` events from the queue of the session.
+///
+/// The oldest events are returned in the order of arrival, and the newest events (if [is_newest]) are returned newest first,
+/// which is how the message queue of mirai-api-http 1.x takes them from its tail.
+/// Bots should not depend on the order of the newest events, a real server doesn't document it.
+fn get_message(world: &mut World, query: &SessionQuery, is_fetch: bool, is_newest: bool) -> WorldResult<Value> {
+    world.bound_bot(&query.session_key)?;
+
+    let session = world.session_mut(&query.session_key)?;
+    let count = query.count.unwrap_or(10).min(session.queue.len());
+
+    let data: Vec<Value> = match (is_fetch, is_newest) {
+        (true, true) => (0..count).filter_map(|_| session.queue.pop_back()).collect(),
+        (true, false) => session.queue.drain(..count).collect(),
+        (false, true) => session.queue.iter().rev().take(count).cloned().collect(),
+        (false, false) => session.queue.iter().take(count).cloned().collect(),
+    };
+
+    Ok(json! {{ "data": data }})
+}
+
+#[get("/fetchMessage")]
+async fn fetch_message(world: WorldData, query: Recorded<SessionQuery>) -> HttpResponse {
+    respond(get_message(&mut world.lock().unwrap(), &query, true, false))
+}
+
+#[get("/fetchLatestMessage")]
+async fn fetch_latest_message(world: WorldData, query: Recorded<SessionQuery>) -> HttpResponse {
+    respond(get_message(&mut world.lock().unwrap(), &query, true, true))
+}
+
+#[get("/peekMessage")]
+async fn peek_message(world: WorldData, query: Recorded<SessionQuery>) -> HttpResponse {
+    respond(get_message(&mut world.lock().unwrap(), &query, false, false))
+}
+
+#[get("/peekLatestMessage")]
+async fn peek_latest_message(world: WorldData, query: Recorded<SessionQuery>) -> HttpResponse {
+    respond(get_message(&mut world.lock().unwrap(), &query, false, true))
+}
+
+#[post("/recall")]
+async fn recall(world: WorldData, req: Recorded<MessageRequest>) -> HttpResponse {
+    respond_common(world.lock().unwrap().recall(&req.session_key, req.target))
+}
+
+#[post("/uploadImage")]
+async fn upload_image(world: WorldData, mut payload: Multipart) -> HttpResponse {
+    let mut session_key = None;
+    let mut image_type = None;
+    let mut has_image = false;
+
+    while let Some(field) = payload.next().await {
+        let mut field = match field {
+            Ok(field) => field,
+            Err(_) => return respond(Err(BAD_REQUEST)),
+        };
+
+        let name = field.content_disposition()
+            .and_then(|cd| cd.get_name().map(String::from));
+
+        let mut bytes = Vec::new();
+
+        while let Some(chunk) = field.next().await {
+            match chunk {
+                Ok(chunk) => bytes.extend_from_slice(&chunk),
+                Err(_) => return respond(Err(BAD_REQUEST)),
+            }
+        }
+
+        match name.as_deref() {
+            Some("sessionKey") => session_key = String::from_utf8(bytes).ok(),
+            Some("type") => image_type = String::from_utf8(bytes).ok(),
+            Some("img") => has_image = !bytes.is_empty(),
+            _ => {}
+        }
+    }
+
+    let mut world = world.lock().unwrap();
+
+    world.record_request("POST", "/uploadImage", json! {{
+        "sessionKey": session_key,
+        "type": image_type,
+    }});
+
+    let result = session_key.ok_or(WRONG_SESSION)
+        .and_then(|key| world.bound_bot(&key).map(|_| ()))
+        .and(if has_image { Ok(()) } else { Err(BAD_REQUEST) })
+        .map(|_| {
+            let image_id = world.next_image_id();
+
+            json! {{
+                "imageId": image_id,
+                "url": format!("http://gchat.qpic.cn/gchatpic_new/0/0-0-{}/0", image_id),
+                "path": "",
+            }}
+        });
+
+    respond_raw(result)
+}
+
+fn do_mute_all(world: &mut World, req: &TargetRequest, muted: bool) -> WorldResult<()> {
+    let group = world.bound_bot_mut(&req.session_key)?.group_mut(req.target)?;
+
+    group.require_admin()?;
+    group.mute_all = muted;
+
+    Ok(())
+}
+
+#[post("/muteAll")]
+async fn mute_all(world: WorldData, req: Recorded<TargetRequest>) -> HttpResponse {
+    respond_common(do_mute_all(&mut world.lock().unwrap(), &req, true))
+}
+
+#[post("/unmuteAll")]
+async fn unmute_all(world: WorldData, req: Recorded<TargetRequest>) -> HttpResponse {
+    respond_common(do_mute_all(&mut world.lock().unwrap(), &req, false))
+}
+
+fn do_mute(world: &mut World, req: &MemberRequest, time: u32) -> WorldResult<()> {
+    if time > MAX_MUTE_TIME {
+        return Err(BAD_REQUEST);
+    }
+
+    let group = world.bound_bot_mut(&req.session_key)?.group_mut(req.target)?;
+
+    group.require_above(req.member_id)?;
+    group.member_mut(req.member_id)?.mute_time_remaining = time;
+
+    Ok(())
+}
+
+#[post("/mute")]
+async fn mute(world: WorldData, req: Recorded<MemberRequest>) -> HttpResponse {
+    respond_common(do_mute(&mut world.lock().unwrap(), &req, req.time.unwrap_or(0)))
+}
+
+#[post("/unmute")]
+async fn unmute(world: WorldData, req: Recorded<MemberRequest>) -> HttpResponse {
+    respond_common(do_mute(&mut world.lock().unwrap(), &req, 0))
+}
+
+fn do_kick(world: &mut World, req: &MemberRequest) -> WorldResult<()> {
+    let group = world.bound_bot_mut(&req.session_key)?.group_mut(req.target)?;
+
+    group.require_above(req.member_id)?;
+    group.members.remove(&req.member_id);
+
+    Ok(())
+}
+
+#[post("/kick")]
+async fn kick(world: WorldData, req: Recorded<MemberRequest>) -> HttpResponse {
+    respond_common(do_kick(&mut world.lock().unwrap(), &req))
+}
+
+fn do_member_admin(world: &mut World, req: &MemberAdminRequest) -> WorldResult<()> {
+    let group = world.bound_bot_mut(&req.session_key)?.group_mut(req.target)?;
+
+    if group.permission != Permission::Owner {
+        return Err(PERMISSION_DENIED);
+    }
+
+    let member = group.member_mut(req.member_id)?;
+    member.permission = if req.assign { Permission::Administrator } else { Permission::Member };
+
+    Ok(())
+}
+
+#[post("/memberAdmin")]
+async fn member_admin(world: WorldData, req: Recorded<MemberAdminRequest>) -> HttpResponse {
+    respond_common(do_member_admin(&mut world.lock().unwrap(), &req))
+}
+
+/// Only the messages which were sent by the bot are kept, so only they can be set as essence messages.
+fn do_set_essence(world: &mut World, req: &MessageRequest) -> WorldResult<()> {
+    let bot = world.bound_bot(&req.session_key)?;
+
+    let group = match world.sent.get(&req.target) {
+        Some(SentMessage { bot: sender, channel: MessageChannel::Group(group), recalled: false, .. }) if *sender == bot.id => *group,
+        _ => return Err(NO_SUCH_TARGET),
+    };
+
+    bot.group(group)?.require_admin()?;
+    world.sent.get_mut(&req.target).ok_or(NO_SUCH_TARGET)?.essence = true;
+
+    Ok(())
+}
+
+#[post("/setEssence")]
+async fn set_essence(world: WorldData, req: Recorded<MessageRequest>) -> HttpResponse {
+    respond_common(do_set_essence(&mut world.lock().unwrap(), &req))
+}
+
+#[get("/anno/list")]
+async fn list_announcements(world: WorldData, query: Recorded<AnnouncementQuery>) -> HttpResponse {
+    let world = world.lock().unwrap();
+
+    let result = world.bound_bot(&query.session_key).and_then(|bot| {
+        let group = bot.group(query.id)?;
+        let announcements: Vec<Value> = group.announcements.iter()
+            .skip(query.offset.unwrap_or(0))
+            .take(query.size.unwrap_or(10))
+            .map(|announcement| group.announcement_json(bot.id, announcement))
+            .collect();
+
+        Ok(json! {{ "data": announcements }})
+    });
+
+    respond(result)
+}
+
+fn do_publish_announcement(world: &mut World, req: &PublishAnnouncementRequest) -> WorldResult<Value> {
+    let fid = format!("{}-{}", req.target, world.next_message_id());
+    let bot = world.bound_bot_mut(&req.session_key)?;
+    let bot_id = bot.id;
+    let group = bot.group_mut(req.target)?;
+
+    group.require_admin()?;
+
+    let announcement = Announcement {
+        fid,
+        content: req.content.clone(),
+        pinned: req.pinned,
+        send_to_new_member: req.send_to_new_member,
+        show_popup: req.show_popup,
+        publication_time: now(),
+    };
+
+    let json = group.announcement_json(bot_id, &announcement);
+    group.announcements.insert(0, announcement);
+
+    Ok(json! {{ "data": json }})
+}
+
+#[post("/anno/publish")]
+async fn publish_announcement(world: WorldData, req: Recorded<PublishAnnouncementRequest>) -> HttpResponse {
+    respond(do_publish_announcement(&mut world.lock().unwrap(), &req))
+}
+
+fn do_delete_announcement(world: &mut World, req: &DeleteAnnouncementRequest) -> WorldResult<()> {
+    let group = world.bound_bot_mut(&req.session_key)?.group_mut(req.id)?;
+
+    group.require_admin()?;
+
+    let index = group.announcements.iter()
+        .position(|announcement| announcement.fid == req.fid)
+        .ok_or(NO_SUCH_TARGET)?;
+
+    group.announcements.remove(index);
+
+    Ok(())
+}
+
+#[post("/anno/delete")]
+async fn delete_announcement(world: WorldData, req: Recorded<DeleteAnnouncementRequest>) -> HttpResponse {
+    respond_common(do_delete_announcement(&mut world.lock().unwrap(), &req))
+}
+
+fn do_quit(world: &mut World, req: &TargetRequest) -> WorldResult<()> {
+    let bot = world.bound_bot_mut(&req.session_key)?;
+
+    if bot.group(req.target)?.permission == Permission::Owner {
+        return Err(PERMISSION_DENIED);
+    }
+
+    bot.groups.remove(&req.target);
+
+    Ok(())
+}
+
+#[post("/quit")]
+async fn quit(world: WorldData, req: Recorded<TargetRequest>) -> HttpResponse {
+    respond_common(do_quit(&mut world.lock().unwrap(), &req))
+}
+
+#[get("/groupConfig")]
+async fn get_group_config(world: WorldData, query: Recorded<SessionQuery>) -> HttpResponse {
+    let world = world.lock().unwrap();
+
+    let result = world.bound_bot(&query.session_key)
+        .and_then(|bot| bot.group(query.target.ok_or(BAD_REQUEST)?))
+        .map(|group| serde_json::to_value(&group.config).unwrap());
+
+    respond_raw(result)
+}
+
+fn do_modify_group_config(world: &mut World, req: &GroupConfigRequest) -> WorldResult<()> {
+    let group = world.bound_bot_mut(&req.session_key)?.group_mut(req.target)?;
+
+    group.require_admin()?;
+
+    let mut config = serde_json::to_value(&group.config).unwrap();
+
+    if let (Value::Object(config), Value::Object(patch)) = (&mut config, &req.config) {
+        for (key, value) in patch {
+            if config.contains_key(key) && !value.is_null() {
+                config.insert(key.clone(), value.clone());
+            }
+        }
+    }
+
+    group.config = serde_json::from_value(config).map_err(|_| BAD_REQUEST)?;
+
+    Ok(())
+}
+
+#[post("/groupConfig")]
+async fn modify_group_config(world: WorldData, req: Recorded<GroupConfigRequest>) -> HttpResponse {
+    respond_common(do_modify_group_config(&mut world.lock().unwrap(), &req))
+}
+
+#[get("/memberInfo")]
+async fn get_member_info(world: WorldData, query: Recorded<SessionQuery>) -> HttpResponse {
+    let world = world.lock().unwrap();
+
+    let result = world.bound_bot(&query.session_key)
+        .and_then(|bot| bot.group(query.target.ok_or(BAD_REQUEST)?))
+        .and_then(|group| group.member(query.member_id.ok_or(BAD_REQUEST)?))
+        .map(|member| json! {{
+            "name": member.name,
+            "specialTitle": member.special_title,
+        }});
+
+    respond_raw(result)
+}
+
+fn do_modify_member_info(world: &mut World, req: &MemberInfoRequest) -> WorldResult<()> {
+    let group = world.bound_bot_mut(&req.session_key)?.group_mut(req.target)?;
+
+    if req.info.special_title.is_some() && group.permission != Permission::Owner {
+        return Err(PERMISSION_DENIED);
+    }
+
+    group.require_above(req.member_id)?;
+
+    let member = group.member_mut(req.member_id)?;
+
+    if let Some(name) = &req.info.name {
+        member.name = name.clone();
+    }
+
+    if let Some(special_title) = &req.info.special_title {
+        member.special_title = special_title.clone();
+    }
+
+    Ok(())
+}
+
+#[post("/memberInfo")]
+async fn modify_member_info(world: WorldData, req: Recorded<MemberInfoRequest>) -> HttpResponse {
+    respond_common(do_modify_member_info(&mut world.lock().unwrap(), &req))
+}
+
+#[get("/config")]
+async fn get_config(world: WorldData, query: Recorded<SessionQuery>) -> HttpResponse {
+    let world = world.lock().unwrap();
+
+    let result = world.session(&query.session_key)
+        .map(|_| serde_json::to_value(&world.config).unwrap());
+
+    respond_raw(result)
+}
+
+#[post("/config")]
+async fn modify_config(world: WorldData, req: Recorded<ConfigRequest>) -> HttpResponse {
+    let mut world = world.lock().unwrap();
+
+    let result = world.session(&req.session_key).map(|_| ());
+
+    if result.is_ok() {
+        if let Some(cache_size) = req.cache_size {
+            world.config.cache_size = cache_size;
+        }
+
+        if let Some(enable_websocket) = req.enable_websocket {
+            world.config.enable_websocket = enable_websocket;
+        }
+    }
+
+    respond_common(result)
+}
+
+#[get("/friendList")]
+async fn friend_list(world: WorldData, query: Recorded<SessionQuery>) -> HttpResponse {
+    let world = world.lock().unwrap();
+
+    let result = world.bound_bot(&query.session_key).map(|bot| {
+        bot.friends.values().map(|friend| json! {{
+            "id": friend.id,
+            "nickname": friend.nickname,
+            "remark": friend.remark,
+        }}).collect()
+    });
+
+    respond_raw(result)
+}
+
+#[get("/groupList")]
+async fn group_list(world: WorldData, query: Recorded<SessionQuery>) -> HttpResponse {
+    let world = world.lock().unwrap();
+
+    let result = world.bound_bot(&query.session_key)
+        .map(|bot| bot.groups.values().map(|group| group.to_json()).collect());
+
+    respond_raw(result)
+}
+
+#[get("/memberList")]
+async fn member_list(world: WorldData, query: Recorded<SessionQuery>) -> HttpResponse {
+    let world = world.lock().unwrap();
+
+    let result = world.bound_bot(&query.session_key)
+        .and_then(|bot| bot.group(query.target.ok_or(BAD_REQUEST)?))
+        .map(|group| group.members.values().map(|member| group.member_json(member)).collect());
+
+    respond_raw(result)
 }
 
 impl MockServer {
@@ -16,16 +814,133 @@ impl MockServer {
         MockServer {
             host: String::from(host.as_ref()),
             port,
+            world: web::Data::new(Mutex::new(World::default())),
         }
     }
 
+    /// Replace the world which the server runs on, the default one is [`World::default`].
+    pub fn world(self, world: World) -> Self {
+        *self.world.lock().unwrap() = world;
+        self
+    }
+
+    /// Return a handle which shares the world with this server,
+    /// it can be used to inject events and read back what the client sent, even after the server started.
+    pub fn handle(&self) -> MockHandle {
+        MockHandle::new(self.world.clone())
+    }
+
     pub async fn start(self) -> std::io::Result<()> {
-        HttpServer::new(|| {
-            App::new()
-                .service(about)
-        })
+        let world = self.world;
+
+        HttpServer::new(move || App::new().app_data(world.clone()).configure(routes))
             .bind(format!("{}:{}", self.host, self.port))?
             .run()
             .await
     }
-}
\ No newline at end of file
+
+    /// Start the server in a new thread, and return a handle to the running server.
+    ///
+    /// The server runs until the handle is dropped, use port 0 to let the system choose a free port,
+    /// so that servers which are spawned by different tests never collide.
+    pub fn spawn(self) -> std::io::Result<SpawnedServer> {
+        let (tx, rx) = mpsc::channel();
+
+        let thread = thread::spawn(move || {
+            let mut system = actix_rt::System::new("mirai_rs_mock");
+            let world = self.world;
+            let server = HttpServer::new(move || App::new().app_data(world.clone()).configure(routes))
+                .shutdown_timeout(0)
+                .system_exit()
+                .bind(format!("{}:{}", self.host, self.port));
+
+            match server {
+                Ok(server) => {
+                    let address = server.addrs()[0];
+                    let server = server.run();
+
+                    let _ = tx.send(Ok((address, server.clone())));
+                    let _ = system.block_on(server);
+                }
+
+                Err(e) => {
+                    let _ = tx.send(Err(e));
+                }
+            }
+        });
+
+        let (address, server) = rx.recv().unwrap()?;
+
+        Ok(SpawnedServer {
+            address,
+            server,
+            thread: Some(thread),
+        })
+    }
+}
+
+/// A mock server which runs in its own thread, see [`MockServer::spawn`].
+///
+/// The server is stopped and its thread is joined when this handle is dropped.
+pub struct SpawnedServer {
+    address: SocketAddr,
+    server: Server,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl SpawnedServer {
+    /// Return the address which the server listens on.
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+}
+
+impl Drop for SpawnedServer {
+    fn drop(&mut self) {
+        // the stop command is sent immediately, the returned future only waits for its completion,
+        // the thread exits after the workers stopped, which close the idle keep-alive connections too
+        drop(self.server.stop(true));
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn routes(config: &mut web::ServiceConfig) {
+    config
+        .service(about)
+        .service(auth)
+        .service(verify)
+        .service(release)
+        .service(send_friend_message)
+        .service(send_group_message)
+        .service(send_temp_message)
+        .service(send_nudge)
+        .service(fetch_message)
+        .service(fetch_latest_message)
+        .service(peek_message)
+        .service(peek_latest_message)
+        .service(recall)
+        .service(upload_image)
+        .service(mute_all)
+        .service(unmute_all)
+        .service(mute)
+        .service(unmute)
+        .service(kick)
+        .service(member_admin)
+        .service(set_essence)
+        .service(list_announcements)
+        .service(publish_announcement)
+        .service(delete_announcement)
+        .service(quit)
+        .service(get_group_config)
+        .service(modify_group_config)
+        .service(get_member_info)
+        .service(modify_member_info)
+        .service(get_config)
+        .service(modify_config)
+        .service(friend_list)
+        .service(group_list)
+        .service(member_list);
+}
@@ -0,0 +1,415 @@
+//! The in-memory world which the mock server runs on.
+//!
+//! A [`World`] contains bots, and each bot has its own friends and groups, just like a real mirai server.
+//! Handlers of the mock server read and modify the world, and answer with the same status codes as mirai-api-http.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use mirai::{Code, Target};
+use mirai::config::Config;
+use mirai::error::{NO_SUCH_BOT, NO_SUCH_TARGET, PERMISSION_DENIED, UNAUTHORIZED, WRONG_AUTH_KEY, WRONG_SESSION};
+use mirai::group::GroupConfig;
+use mirai::message::{MessageChain, MessageChannel, MessageID, TimeStamp};
+
+pub type WorldResult<T> = Result<T, Code>;
+
+#[derive(Serialize, Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Permission {
+    #[serde(rename = "MEMBER")]
+    Member,
+
+    #[serde(rename = "ADMINISTRATOR")]
+    Administrator,
+
+    #[serde(rename = "OWNER")]
+    Owner,
+}
+
+#[derive(Clone, Debug)]
+pub struct Friend {
+    pub id: Target,
+    pub nickname: String,
+    pub remark: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct Member {
+    pub id: Target,
+    pub name: String,
+    pub special_title: String,
+    pub permission: Permission,
+    /// In seconds, it is not reported in `/memberInfo`, which is the same as mirai-api-http 1.x.
+    pub mute_time_remaining: u32,
+}
+
+/// An announcement of a group, the bot is the sender of all the announcements.
+#[derive(Clone, Debug)]
+pub struct Announcement {
+    pub fid: String,
+    pub content: String,
+    pub pinned: bool,
+    pub send_to_new_member: bool,
+    pub show_popup: bool,
+    pub publication_time: TimeStamp,
+}
+
+#[derive(Clone, Debug)]
+pub struct Group {
+    pub id: Target,
+    pub config: GroupConfig,
+    /// The permission of the bot in this group.
+    pub permission: Permission,
+    /// It is not reported in `/groupConfig`, which is the same as mirai-api-http 1.x.
+    pub mute_all: bool,
+    pub bot_muted: bool,
+    pub members: HashMap<Target, Member>,
+    pub announcements: Vec<Announcement>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Bot {
+    pub id: Target,
+    pub nickname: String,
+    pub friends: HashMap<Target, Friend>,
+    pub groups: HashMap<Target, Group>,
+}
+
+/// A message which was sent by a bot, it is kept for recalling.
+#[derive(Clone, Debug)]
+pub struct SentMessage {
+    pub id: MessageID,
+    pub bot: Target,
+    pub channel: MessageChannel,
+    pub message_chain: MessageChain,
+    pub quote: Option<MessageID>,
+    pub time: TimeStamp,
+    pub recalled: bool,
+    /// Whether the message was set as an essence message of its group.
+    pub essence: bool,
+}
+
+/// A request which was sent by the client, GET requests are recorded with their queries as the body.
+#[derive(Clone, Debug)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub body: Value,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct MockSession {
+    pub bound: Option<Target>,
+    pub queue: VecDeque<Value>,
+}
+
+#[derive(Clone, Debug)]
+pub struct World {
+    pub auth_key: String,
+    pub config: Config,
+    pub bots: HashMap<Target, Bot>,
+    pub sessions: HashMap<String, MockSession>,
+    pub sent: HashMap<MessageID, SentMessage>,
+    pub requests: Vec<RecordedRequest>,
+    next_session: u64,
+    next_message_id: MessageID,
+    next_image_id: u64,
+}
+
+pub fn now() -> TimeStamp {
+    SystemTime::now().duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+impl Member {
+    pub fn new<S: AsRef<str>>(id: Target, name: S, permission: Permission) -> Member {
+        Member {
+            id,
+            name: name.as_ref().to_string(),
+            special_title: String::new(),
+            permission,
+            mute_time_remaining: 0,
+        }
+    }
+}
+
+impl Group {
+    pub fn new<S: AsRef<str>>(id: Target, name: S, permission: Permission) -> Group {
+        Group {
+            id,
+            config: GroupConfig {
+                name: name.as_ref().to_string(),
+                announcement: String::new(),
+                confess_talk: false,
+                allow_member_invite: false,
+                auto_approve: false,
+                anonymous_chat: false,
+                mute_all: None,
+            },
+            permission,
+            mute_all: false,
+            bot_muted: false,
+            members: HashMap::new(),
+            announcements: Vec::new(),
+        }
+    }
+
+    pub fn with_member(mut self, member: Member) -> Self {
+        self.members.insert(member.id, member);
+        self
+    }
+
+    pub fn member(&self, id: Target) -> WorldResult<&Member> {
+        self.members.get(&id).ok_or(NO_SUCH_TARGET)
+    }
+
+    pub fn member_mut(&mut self, id: Target) -> WorldResult<&mut Member> {
+        self.members.get_mut(&id).ok_or(NO_SUCH_TARGET)
+    }
+
+    /// Check that the bot is an administrator (or the owner) of this group.
+    pub fn require_admin(&self) -> WorldResult<()> {
+        if self.permission > Permission::Member {
+            Ok(())
+        } else {
+            Err(PERMISSION_DENIED)
+        }
+    }
+
+    /// Check that the bot has a higher permission than the given member.
+    pub fn require_above(&self, member: Target) -> WorldResult<&Member> {
+        let member = self.member(member)?;
+
+        if self.permission > member.permission {
+            Ok(member)
+        } else {
+            Err(PERMISSION_DENIED)
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json! {{
+            "id": self.id,
+            "name": self.config.name,
+            "permission": self.permission,
+        }}
+    }
+
+    pub fn announcement_json(&self, bot: Target, announcement: &Announcement) -> Value {
+        json! {{
+            "group": self.to_json(),
+            "content": announcement.content,
+            "senderId": bot,
+            "fid": announcement.fid,
+            "allConfirmed": false,
+            "confirmedMembersCount": 0,
+            "publicationTime": announcement.publication_time,
+        }}
+    }
+
+    pub fn member_json(&self, member: &Member) -> Value {
+        json! {{
+            "id": member.id,
+            "memberName": member.name,
+            "permission": member.permission,
+            "group": self.to_json(),
+        }}
+    }
+}
+
+impl Bot {
+    pub fn new<S: AsRef<str>>(id: Target, nickname: S) -> Bot {
+        Bot {
+            id,
+            nickname: nickname.as_ref().to_string(),
+            friends: HashMap::new(),
+            groups: HashMap::new(),
+        }
+    }
+
+    pub fn with_friend<S: AsRef<str>>(mut self, id: Target, nickname: S) -> Self {
+        self.friends.insert(id, Friend {
+            id,
+            nickname: nickname.as_ref().to_string(),
+            remark: nickname.as_ref().to_string(),
+        });
+        self
+    }
+
+    pub fn with_group(mut self, group: Group) -> Self {
+        self.groups.insert(group.id, group);
+        self
+    }
+
+    pub fn friend(&self, id: Target) -> WorldResult<&Friend> {
+        self.friends.get(&id).ok_or(NO_SUCH_TARGET)
+    }
+
+    pub fn group(&self, id: Target) -> WorldResult<&Group> {
+        self.groups.get(&id).ok_or(NO_SUCH_TARGET)
+    }
+
+    pub fn group_mut(&mut self, id: Target) -> WorldResult<&mut Group> {
+        self.groups.get_mut(&id).ok_or(NO_SUCH_TARGET)
+    }
+}
+
+impl World {
+    pub fn new<S: AsRef<str>>(auth_key: S) -> World {
+        World {
+            auth_key: auth_key.as_ref().to_string(),
+            config: Config {
+                cache_size: 4096,
+                enable_websocket: false,
+            },
+            bots: HashMap::new(),
+            sessions: HashMap::new(),
+            sent: HashMap::new(),
+            requests: Vec::new(),
+            next_session: 0,
+            next_message_id: 1,
+            next_image_id: 0,
+        }
+    }
+
+    pub fn with_bot(mut self, bot: Bot) -> Self {
+        self.bots.insert(bot.id, bot);
+        self
+    }
+
+    /// Create a new session if the auth key is correct.
+    pub fn auth(&mut self, auth_key: &str) -> WorldResult<String> {
+        if auth_key != self.auth_key {
+            return Err(WRONG_AUTH_KEY);
+        }
+
+        self.next_session += 1;
+
+        let key = format!("MOCK_SESSION_{}", self.next_session);
+        self.sessions.insert(key.clone(), MockSession::default());
+
+        Ok(key)
+    }
+
+    pub fn verify(&mut self, session_key: &str, qq: Target) -> WorldResult<()> {
+        if !self.bots.contains_key(&qq) {
+            return Err(NO_SUCH_BOT);
+        }
+
+        let session = self.sessions.get_mut(session_key).ok_or(WRONG_SESSION)?;
+        session.bound = Some(qq);
+
+        Ok(())
+    }
+
+    pub fn release(&mut self, session_key: &str, qq: Target) -> WorldResult<()> {
+        match self.sessions.get(session_key) {
+            None => Err(WRONG_SESSION),
+            // a session which is not bound can be released with any bot
+            Some(session) if session.bound.is_some() && session.bound != Some(qq) => Err(NO_SUCH_BOT),
+            Some(_) => {
+                self.sessions.remove(session_key);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn session(&self, session_key: &str) -> WorldResult<&MockSession> {
+        self.sessions.get(session_key).ok_or(WRONG_SESSION)
+    }
+
+    pub fn session_mut(&mut self, session_key: &str) -> WorldResult<&mut MockSession> {
+        self.sessions.get_mut(session_key).ok_or(WRONG_SESSION)
+    }
+
+    /// Return the bot which the given session is bound with.
+    pub fn bound_bot(&self, session_key: &str) -> WorldResult<&Bot> {
+        let qq = self.session(session_key)?.bound.ok_or(UNAUTHORIZED)?;
+
+        self.bots.get(&qq).ok_or(NO_SUCH_BOT)
+    }
+
+    pub fn bound_bot_mut(&mut self, session_key: &str) -> WorldResult<&mut Bot> {
+        let qq = self.session(session_key)?.bound.ok_or(UNAUTHORIZED)?;
+
+        self.bots.get_mut(&qq).ok_or(NO_SUCH_BOT)
+    }
+
+    /// Push an event to every session which is bound with the given bot.
+    pub fn push_event(&mut self, bot: Target, event: Value) {
+        for session in self.sessions.values_mut() {
+            if session.bound == Some(bot) {
+                session.queue.push_back(event.clone());
+            }
+        }
+    }
+
+    /// Allocate an id for a message, both sent and received messages share the same ids.
+    pub fn next_message_id(&mut self) -> MessageID {
+        let id = self.next_message_id;
+        self.next_message_id += 1;
+
+        id
+    }
+
+    /// Record a message which is sent by the given bot, and return its id.
+    pub fn record_message(&mut self, bot: Target, channel: MessageChannel, message_chain: MessageChain, quote: Option<MessageID>) -> MessageID {
+        let id = self.next_message_id();
+
+        self.sent.insert(id, SentMessage {
+            id,
+            bot,
+            channel,
+            message_chain,
+            quote,
+            time: now(),
+            recalled: false,
+            essence: false,
+        });
+
+        id
+    }
+
+    pub fn record_request(&mut self, method: &str, path: &str, body: Value) {
+        self.requests.push(RecordedRequest {
+            method: method.to_string(),
+            path: path.to_string(),
+            body,
+        });
+    }
+
+    pub fn recall(&mut self, session_key: &str, message_id: MessageID) -> WorldResult<()> {
+        let bot = self.bound_bot(session_key)?.id;
+
+        match self.sent.get_mut(&message_id) {
+            Some(message) if message.bot == bot && !message.recalled => {
+                message.recalled = true;
+                Ok(())
+            }
+
+            _ => Err(NO_SUCH_TARGET)
+        }
+    }
+
+    pub fn next_image_id(&mut self) -> String {
+        self.next_image_id += 1;
+
+        format!("{{{:08X}-0000-0000-0000-000000000000}}.png", self.next_image_id)
+    }
+}
+
+impl Default for World {
+    /// A world which contains a bot with a friend and a group, the auth key is `"mirai_rs_mock"`.
+    fn default() -> Self {
+        World::new("mirai_rs_mock")
+            .with_bot(Bot::new(1, "Mock Bot")
+                .with_friend(10, "Mock Friend")
+                .with_group(Group::new(100, "Mock Group", Permission::Owner)
+                    .with_member(Member::new(10, "Mock Friend", Permission::Administrator))
+                    .with_member(Member::new(11, "Mock Member", Permission::Member))))
+    }
+}
@@ -6,7 +6,7 @@ use std::io::stdin;
 use reqwest::{Client, Proxy};
 
 pub async fn connect_server(client: Client) -> MiraiConnection {
-    let connection = MiraiConnection::new("http://localhost:8080", client);
+    let connection = MiraiConnection::new("http://localhost:8080", client).unwrap();
 
     loop {
         println!("Try to connecting to server: {}", connection.base_url());
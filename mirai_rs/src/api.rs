@@ -0,0 +1,153 @@
+//! This mod provides the typed endpoint layer which every API call goes through.
+//!
+//! # ApiRequest
+//!
+//! An [`ApiRequest`] describes an endpoint of mirai-api-http: its path, its method, and the type of its response.
+//! The fields of the request are the body of a POST request, or the query of a GET request.
+//! A request which uploads files (see [`ApiRequest::files`]) is sent as a multipart form, the fields are text parts of it.
+//!
+//! ```ignore
+//! #[derive(Serialize)]
+//! struct Request {
+//!     target: Target,
+//! }
+//!
+//! impl ApiRequest for Request {
+//!     type Response = CommonResponse;
+//!
+//!     fn path(&self) -> &str { "/muteAll" }
+//!     fn action(&self) -> &str { "MuteAll" }
+//! }
+//!
+//! session.call(&Request { target: group_id }).await?;
+//! ```
+//!
+//! [`Session::call`] adds the session key to the request, sends it by the [`Transport`] of the connection,
+//! and fails with a [`StatusCodeError`] if the response has a non-zero `code`, so endpoints don't handle these by themselves.
+//!
+//! [`Session::call`]: crate::session::Session::call
+//! [`Transport`]: crate::transport::Transport
+//! [`StatusCodeError`]: crate::error::StatusCodeError
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::Code;
+use crate::error::{assert, HttpResult};
+use crate::secret::SecretString;
+use crate::session::MiraiConnection;
+use crate::transport::{Body, Field, Method, Request};
+
+pub trait ApiRequest: Serialize {
+    type Response: DeserializeOwned;
+
+    /// The path of the endpoint, it starts with the '/' separator.
+    fn path(&self) -> &str;
+
+    /// The name of the action, which is reported in [`StatusCodeError`](crate::error::StatusCodeError).
+    fn action(&self) -> &str;
+
+    fn method(&self) -> Method {
+        Method::Post
+    }
+
+    /// The files which are uploaded with the fields, a POST request with files is sent as a multipart form.
+    fn files(&self) -> Vec<(String, Field)> {
+        Vec::new()
+    }
+
+    /// Deserialize the response from the whole body, the status code has been checked.
+    fn parse(response: Value) -> serde_json::Result<Self::Response> where Self: Sized {
+        serde_json::from_value(response)
+    }
+}
+
+/// Deserialize the `data` of a response which is wrapped with a status code like `{ "code": 0, "data": .. }`,
+/// responses which are not wrapped (such as lists in mirai-api-http 1.x) are deserialized directly.
+pub(crate) fn parse_data<T: DeserializeOwned>(response: Value) -> serde_json::Result<T> {
+    match response {
+        Value::Object(mut response) if response.contains_key("code") => serde_json::from_value(response.remove("data").unwrap_or_default()),
+        response => serde_json::from_value(response),
+    }
+}
+
+/// Return the fields of a request as query parameters, strings are not quoted.
+fn query(fields: &Value) -> Vec<(String, String)> {
+    let fields = match fields {
+        Value::Object(fields) => fields,
+        _ => return Vec::new(),
+    };
+
+    fields.iter()
+        .filter(|(_, value)| !value.is_null())
+        .map(|(name, value)| {
+            let value = value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string());
+
+            (name.clone(), value)
+        })
+        .collect()
+}
+
+impl MiraiConnection {
+    /// Send the [request] with the given session key (if any), check the status code and deserialize the response.
+    pub(crate) async fn call_with_key<R: ApiRequest>(&self, session_key: Option<&SecretString>, request: &R) -> HttpResult<R::Response> {
+        let fields = serde_json::to_value(request)?;
+
+        #[cfg(feature = "tracing")]
+        let span = crate::trace::api_span(request.path(), request.action(), &fields);
+
+        let future = self.call_fields(session_key, request, fields);
+
+        #[cfg(feature = "tracing")]
+        let future = tracing::Instrument::instrument(future, span);
+
+        future.await
+    }
+
+    async fn call_fields<R: ApiRequest>(&self, session_key: Option<&SecretString>, request: &R, fields: Value) -> HttpResult<R::Response> {
+        let files = request.files();
+
+        let raw = match request.method() {
+            Method::Get => Request::new(Method::Get, request.path(), Body::Empty).with_query(query(&fields)),
+
+            Method::Post if files.is_empty() => Request::new(Method::Post, request.path(), Body::Json(fields)),
+
+            Method::Post => {
+                let form = query(&fields).into_iter()
+                    .map(|(name, value)| (name, Field::Text(value)))
+                    .chain(files)
+                    .collect();
+
+                Request::new(Method::Post, request.path(), Body::Multipart(form))
+            }
+        };
+
+        // the session key is inserted only when the request is sent, see `transport` mod
+        let raw = match session_key {
+            Some(session_key) => raw.with_session_key(session_key.clone()),
+            None => raw,
+        };
+
+        let response: Value = self.request(raw).await?;
+
+        if let Some(code) = response.get("code").and_then(Value::as_u64) {
+            #[cfg(feature = "tracing")]
+            crate::trace::record_code(code as Code);
+
+            #[cfg(feature = "metrics")]
+            if code as Code != crate::error::SUCCESS {
+                crate::metrics::registry().api_error(code as Code);
+            }
+
+            assert(code as Code, request.action())?;
+        }
+
+        Ok(R::parse(response)?)
+    }
+
+    /// Send a [request] which doesn't need a session.
+    pub async fn call<R: ApiRequest>(&self, request: &R) -> HttpResult<R::Response> {
+        self.call_with_key(None, request).await
+    }
+}
@@ -0,0 +1,145 @@
+//! This mod provides moderation operations on many members at once, such as muting all the accounts of a spam raid.
+//!
+//! # Batch
+//!
+//! A batch operation sends one request per member, at most [`BatchOptions::concurrency`] of them are in flight at the same time,
+//! and two requests are started at least [`BatchOptions::interval`] apart, so the server doesn't rate limit the bot.
+//!
+//! A failure doesn't stop the batch, the result of every member is collected in a [`BatchReport`]:
+//!
+//! ```ignore
+//! let report = session.kick_many(group_id, &spammers, "spam", &BatchOptions::default()).await;
+//!
+//! for (member, error) in report.failed() {
+//!     println!("Failed to kick {}: {}", member, error);
+//! }
+//! ```
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use futures::stream::{self, StreamExt};
+use tokio::sync::Mutex;
+
+use crate::Target;
+use crate::error::{HttpError, HttpResult};
+use crate::session::Session;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct BatchOptions {
+    pub(crate) concurrency: usize,
+    pub(crate) interval: Duration,
+}
+
+impl Default for BatchOptions {
+    /// 4 requests in flight, and 100 milliseconds between requests.
+    fn default() -> Self {
+        BatchOptions {
+            concurrency: 4,
+            interval: Duration::from_millis(100),
+        }
+    }
+}
+
+impl BatchOptions {
+    pub fn new() -> BatchOptions {
+        BatchOptions::default()
+    }
+
+    /// Set the maximum count of requests which are in flight at the same time, it is at least 1.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Set the minimum interval between the starts of two requests, use [`Duration::from_secs(0)`] to disable rate limiting.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+}
+
+/// The results of a batch operation, in the order of the given members.
+#[derive(Debug)]
+pub struct BatchReport {
+    results: Vec<(Target, HttpResult<()>)>,
+}
+
+impl BatchReport {
+    pub fn results(&self) -> &[(Target, HttpResult<()>)] {
+        &self.results
+    }
+
+    pub fn into_results(self) -> Vec<(Target, HttpResult<()>)> {
+        self.results
+    }
+
+    /// Return the members which the operation succeeded on.
+    pub fn succeeded(&self) -> Vec<Target> {
+        self.results.iter()
+            .filter(|(_, result)| result.is_ok())
+            .map(|(member, _)| *member)
+            .collect()
+    }
+
+    /// Return the members which the operation failed on, and their errors.
+    pub fn failed(&self) -> Vec<(Target, &HttpError)> {
+        self.results.iter()
+            .filter_map(|(member, result)| result.as_ref().err().map(|error| (*member, error)))
+            .collect()
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.results.iter().all(|(_, result)| result.is_ok())
+    }
+}
+
+/// Run [operation] on every member in [targets], with the concurrency and rate limit of [options].
+async fn run<F, Fut>(targets: &[Target], options: &BatchOptions, operation: F) -> BatchReport where
+    F: Fn(Target) -> Fut,
+    Fut: Future<Output = HttpResult<()>> {
+    let next_start = Mutex::new(Instant::now());
+    let interval = options.interval;
+
+    let results = stream::iter(targets.iter().copied())
+        .map(|target| {
+            let next_start = &next_start;
+            let request = operation(target);
+
+            async move {
+                let start = {
+                    let mut next_start = next_start.lock().await;
+                    let start = (*next_start).max(Instant::now());
+                    *next_start = start + interval;
+
+                    start
+                };
+
+                tokio::time::delay_until(start.into()).await;
+
+                (target, request.await)
+            }
+        })
+        .buffered(options.concurrency)
+        .collect()
+        .await;
+
+    BatchReport { results }
+}
+
+impl Session {
+    /// Mute every member in [targets] for [duration], see [`Session::mute`].
+    pub async fn mute_many(&self, group_id: Target, targets: &[Target], duration: Duration, options: &BatchOptions) -> BatchReport {
+        run(targets, options, |target| self.mute(group_id, target, duration)).await
+    }
+
+    /// Unmute every member in [targets], see [`Session::unmute`].
+    pub async fn unmute_many(&self, group_id: Target, targets: &[Target], options: &BatchOptions) -> BatchReport {
+        run(targets, options, |target| self.unmute(group_id, target)).await
+    }
+
+    /// Kick every member in [targets] with the message [msg], see [`Session::kick`].
+    pub async fn kick_many(&self, group_id: Target, targets: &[Target], msg: &str, options: &BatchOptions) -> BatchReport {
+        run(targets, options, |target| self.kick(group_id, target, msg)).await
+    }
+}
@@ -0,0 +1,304 @@
+//! This mod provides a cache of the contacts of a bot, so that handlers can resolve ids and names without hitting the server.
+//!
+//! # ContactCache
+//!
+//! A [`ContactCache`] loads the friend list, the group list and the member list of each group lazily,
+//! that is, a list is requested from the server the first time it is looked up, and is kept since then.
+//!
+//! The cached lists are kept up to date by feeding the received events to [`ContactCache::update`]:
+//!
+//! ```ignore
+//! let cache = ContactCache::new();
+//!
+//! for packet in session.fetch_newest_message(10).await? {
+//!     cache.update(&packet);
+//!
+//!     if let Some(member) = cache.member_by_name(&session, group_id, "HoshinoTented").await? {
+//!         // ...
+//!     }
+//! }
+//! ```
+//!
+//! The following events update the cache:
+//!
+//! * [`MemberEvent`]: a member joins or leaves a group, or changes its name card or permission.
+//! * [`BotGroupEvent`]: the bot joins or leaves a group, the members of a group which the bot left are dropped.
+//! * [`BotGroupPermissionChangeEvent`]: the permission of the bot in a group is changed.
+//! * [`GroupChangeEvent`]: the name of a group is changed.
+//!
+//! The updates which arrive while a list is being requested are applied to it after it is received,
+//! so that the list is not older than the updates.
+//!
+//! Changes which are not reported by events (such as new friends) can be reloaded by [`ContactCache::invalidate`].
+//!
+//! [`BotGroupEvent`]: crate::message::event::BotGroupEvent
+//! [`BotGroupPermissionChangeEvent`]: crate::message::event::BotGroupPermissionChangeEvent
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use crate::Target;
+use crate::error::HttpResult;
+use crate::message::EventPacket;
+use crate::message::element::{FriendMember, Group, GroupMember};
+use crate::message::event::{ChangeType, GroupChangeEvent, GroupChangeEventKind, MemberEvent};
+use crate::session::Session;
+
+#[derive(Debug, Default)]
+pub struct ContactCache {
+    friends: Mutex<Option<BTreeMap<Target, FriendMember>>>,
+    groups: Mutex<Option<BTreeMap<Target, Group>>>,
+    members: Mutex<BTreeMap<Target, BTreeMap<Target, GroupMember>>>,
+    pending: Mutex<Pending>,
+}
+
+/// The updates which arrived while lists are being requested, it is locked before any of the lists.
+#[derive(Debug, Default)]
+struct Pending {
+    /// The count of lists which are being requested.
+    loading: usize,
+    /// The updates since the first of the requests was sent, they are dropped when all of the requests finish.
+    packets: Vec<EventPacket>,
+}
+
+/// A list which is being requested, the updates are kept until it is dropped.
+struct Loading<'c> {
+    pending: &'c Mutex<Pending>,
+    /// The index of the first update which arrived after the request was sent.
+    start: usize,
+}
+
+impl Loading<'_> {
+    /// Call [f] with the updates which arrived since the request was sent, no more updates arrive until [f] returns.
+    fn finish<R, F: FnOnce(&[EventPacket]) -> R>(&self, f: F) -> R {
+        let pending = self.pending.lock().unwrap();
+
+        f(&pending.packets[self.start..])
+    }
+}
+
+impl Drop for Loading<'_> {
+    fn drop(&mut self) {
+        let mut pending = self.pending.lock().unwrap();
+        pending.loading -= 1;
+
+        if pending.loading == 0 {
+            pending.packets.clear();
+        }
+    }
+}
+
+fn by_id<T, F: Fn(&T) -> Target>(list: Vec<T>, id: F) -> BTreeMap<Target, T> {
+    list.into_iter().map(|item| (id(&item), item)).collect()
+}
+
+/// Return the new name of the group if the [event] renames it.
+fn new_name(event: &GroupChangeEvent) -> Option<&String> {
+    match (event.kind(), event.change()) {
+        (GroupChangeEventKind::GroupNameChangeEvent, ChangeType::String { current, .. }) => Some(current),
+        _ => None,
+    }
+}
+
+fn update_groups(groups: &mut BTreeMap<Target, Group>, packet: &EventPacket) {
+    match packet {
+        EventPacket::BotGroupEvent(event) if event.is_join() => {
+            groups.insert(event.group().id, event.group().clone());
+        }
+
+        EventPacket::BotGroupEvent(event) => {
+            groups.remove(&event.group().id);
+        }
+
+        EventPacket::BotGroupPermissionChangeEvent(event) => {
+            if let Some(group) = groups.get_mut(&event.group().id) {
+                group.permission = event.current().clone();
+            }
+        }
+
+        EventPacket::GroupChangeEvent(event) => {
+            if let (Some(name), Some(group)) = (new_name(event), groups.get_mut(&event.group().id)) {
+                group.name = name.clone();
+            }
+        }
+
+        _ => {}
+    }
+}
+
+/// Update the member list of the [group] with the [packet],
+/// return `false` if the bot left the group, then the list should be dropped.
+fn update_members(group: Target, members: &mut BTreeMap<Target, GroupMember>, packet: &EventPacket) -> bool {
+    match packet {
+        EventPacket::MemberEvent(event) if event.member().group.id == group => update_member(members, event),
+
+        EventPacket::BotGroupEvent(event) if event.group().id == group => return event.is_join(),
+
+        EventPacket::BotGroupPermissionChangeEvent(event) if event.group().id == group => {
+            members.values_mut().for_each(|member| member.group.permission = event.current().clone());
+        }
+
+        EventPacket::GroupChangeEvent(event) if event.group().id == group => {
+            if let Some(name) = new_name(event) {
+                members.values_mut().for_each(|member| member.group.name = name.clone());
+            }
+        }
+
+        _ => {}
+    }
+
+    true
+}
+
+fn update_member(members: &mut BTreeMap<Target, GroupMember>, event: &MemberEvent) {
+    let member = event.member();
+
+    match event {
+        MemberEvent::MemberLeaveEventKick { .. } | MemberEvent::MemberLeaveEventQuit { .. } => {
+            members.remove(&member.id);
+        }
+
+        MemberEvent::MemberJoinEvent { .. } => {
+            members.insert(member.id, member.clone());
+        }
+
+        MemberEvent::MemberCardChangeEvent { current, .. } => {
+            let mut member = member.clone();
+            member.member_name = current.clone();
+            members.insert(member.id, member);
+        }
+
+        MemberEvent::MemberPermissionChangeEvent { current, .. } => {
+            let mut member = member.clone();
+            member.permission = current.clone();
+            members.insert(member.id, member);
+        }
+    }
+}
+
+impl ContactCache {
+    pub fn new() -> ContactCache {
+        ContactCache::default()
+    }
+
+    /// Return the friend list, it is requested from the server if it is not cached.
+    pub async fn friends(&self, session: &Session) -> HttpResult<Vec<FriendMember>> {
+        if let Some(friends) = self.friends.lock().unwrap().as_ref() {
+            return Ok(friends.values().cloned().collect());
+        }
+
+        let loading = self.start_loading();
+        let friends = session.friend_list().await?;
+
+        // no updates change friends
+        loading.finish(|_| *self.friends.lock().unwrap() = Some(by_id(friends.clone(), FriendMember::id)));
+
+        Ok(friends)
+    }
+
+    /// Return the group list, it is requested from the server if it is not cached.
+    pub async fn groups(&self, session: &Session) -> HttpResult<Vec<Group>> {
+        if let Some(groups) = self.groups.lock().unwrap().as_ref() {
+            return Ok(groups.values().cloned().collect());
+        }
+
+        let loading = self.start_loading();
+        let groups = session.group_list().await?;
+
+        Ok(loading.finish(|packets| {
+            let mut groups = by_id(groups, Group::id);
+            packets.iter().for_each(|packet| update_groups(&mut groups, packet));
+            *self.groups.lock().unwrap() = Some(groups.clone());
+
+            groups.into_values().collect()
+        }))
+    }
+
+    /// Return the member list of the [group], it is requested from the server if it is not cached.
+    pub async fn members(&self, session: &Session, group: Target) -> HttpResult<Vec<GroupMember>> {
+        if let Some(members) = self.members.lock().unwrap().get(&group) {
+            return Ok(members.values().cloned().collect());
+        }
+
+        let loading = self.start_loading();
+        let members = session.group_member_list(group).await?;
+
+        Ok(loading.finish(|packets| {
+            let mut members = by_id(members, GroupMember::id);
+
+            // the list of a group which the bot left is not cached
+            if packets.iter().all(|packet| update_members(group, &mut members, packet)) {
+                self.members.lock().unwrap().insert(group, members.clone());
+            }
+
+            members.into_values().collect()
+        }))
+    }
+
+    pub async fn friend(&self, session: &Session, id: Target) -> HttpResult<Option<FriendMember>> {
+        Ok(self.friends(session).await?.into_iter().find(|friend| friend.id == id))
+    }
+
+    /// Find a friend whose remark or nickname is [name], the remark is preferred.
+    pub async fn friend_by_name(&self, session: &Session, name: &str) -> HttpResult<Option<FriendMember>> {
+        let friends = self.friends(session).await?;
+        let by_remark = friends.iter().position(|friend| friend.remark == name);
+        let index = by_remark.or_else(|| friends.iter().position(|friend| friend.nick_name == name));
+
+        Ok(index.map(|index| friends[index].clone()))
+    }
+
+    pub async fn group(&self, session: &Session, id: Target) -> HttpResult<Option<Group>> {
+        Ok(self.groups(session).await?.into_iter().find(|group| group.id == id))
+    }
+
+    pub async fn group_by_name(&self, session: &Session, name: &str) -> HttpResult<Option<Group>> {
+        Ok(self.groups(session).await?.into_iter().find(|group| group.name == name))
+    }
+
+    pub async fn member(&self, session: &Session, group: Target, id: Target) -> HttpResult<Option<GroupMember>> {
+        Ok(self.members(session, group).await?.into_iter().find(|member| member.id == id))
+    }
+
+    /// Find a member of the [group] whose name card is [name].
+    pub async fn member_by_name(&self, session: &Session, group: Target, name: &str) -> HttpResult<Option<GroupMember>> {
+        Ok(self.members(session, group).await?.into_iter().find(|member| member.member_name == name))
+    }
+
+    /// Drop all the cached lists, they are requested again when they are looked up.
+    pub fn invalidate(&self) {
+        *self.friends.lock().unwrap() = None;
+        *self.groups.lock().unwrap() = None;
+        self.members.lock().unwrap().clear();
+    }
+
+    /// Drop the cached member list of the [group].
+    pub fn invalidate_members(&self, group: Target) {
+        self.members.lock().unwrap().remove(&group);
+    }
+
+    /// Update the cached lists with the [packet], the lists which are not cached yet are not affected.
+    pub fn update(&self, packet: &EventPacket) {
+        let mut pending = self.pending.lock().unwrap();
+
+        if pending.loading > 0 {
+            pending.packets.push(packet.clone());
+        }
+
+        if let Some(groups) = self.groups.lock().unwrap().as_mut() {
+            update_groups(groups, packet);
+        }
+
+        self.members.lock().unwrap().retain(|group, members| update_members(*group, members, packet));
+    }
+
+    fn start_loading(&self) -> Loading<'_> {
+        let mut pending = self.pending.lock().unwrap();
+        pending.loading += 1;
+
+        Loading {
+            pending: &self.pending,
+            start: pending.packets.len(),
+        }
+    }
+}
@@ -1,28 +1,27 @@
 use serde::Serialize;
 
+use crate::api::ApiRequest;
 use crate::session::{Session, CommonResponse};
 use crate::message::MessageID;
-use crate::error::{HttpResult, assert};
+use crate::error::HttpResult;
 
 /// Others
 impl Session {
     pub async fn recall(&self, message_id: MessageID) -> HttpResult<()> {
         #[derive(Serialize)]
         struct Request {
-            #[serde(rename = "sessionKey")]
-            session_key: String,
             target: MessageID,
         }
 
-        let req = Request {
-            session_key: self.key.clone(),
-            target: message_id,
-        };
+        impl ApiRequest for Request {
+            type Response = CommonResponse;
 
-        let resp: CommonResponse = self.client().post(&self.url("/recall"))
-            .json(&req).send().await?
-            .json().await?;
+            fn path(&self) -> &str { "/recall" }
+            fn action(&self) -> &str { "Recall" }
+        }
+
+        self.call(&Request { target: message_id }).await?;
 
-        assert(resp.code, "Recall")
+        Ok(())
     }
 }
\ No newline at end of file
@@ -7,11 +7,15 @@
 //! * cash_size: the cashsize of mirai-api-http server, too small cache will lead to failure of reference reply and recall messages
 //! * enable_websocket: whether websocket is open
 //!
+//! Note that the session config was removed in mirai-api-http 2.x, these functions only work with 1.x servers.
+//!
 
 use serde::{Deserialize, Serialize};
 
-use crate::error::{assert, HttpResult};
+use crate::api::ApiRequest;
+use crate::error::HttpResult;
 use crate::session::{CommonResponse, Session};
+use crate::transport::Method;
 use crate::CacheSize;
 
 #[serde(rename_all = "camelCase")]
@@ -24,31 +28,36 @@ pub struct Config {
 impl Session {
     /// Return config of mirai-api-http server.
     pub async fn get_config(&self) -> HttpResult<Config> {
-        let config: Config = self.client().get(&self.url(&format!("/config?sessionKey={}", self.key)))
-            .send().await?
-            .json().await?;
+        #[derive(Serialize)]
+        struct Request {}
 
-        Ok(config)
+        impl ApiRequest for Request {
+            type Response = Config;
+
+            fn path(&self) -> &str { "/config" }
+            fn action(&self) -> &str { "GetConfig" }
+            fn method(&self) -> Method { Method::Get }
+        }
+
+        self.call(&Request {}).await
     }
     /// Return the result of modify mirai-api-http server.
     pub async fn modify_config(&self, new_config: Config) -> HttpResult<()> {
-        #[serde(rename_all = "camelCase")]
         #[derive(Serialize)]
         struct Request {
-            session_key: String,
             #[serde(flatten)]
             config: Config,
         }
 
-        let req = Request {
-            session_key: self.key.clone(),
-            config: new_config,
-        };
+        impl ApiRequest for Request {
+            type Response = CommonResponse;
+
+            fn path(&self) -> &str { "/config" }
+            fn action(&self) -> &str { "ModifyConfig" }
+        }
 
-        let resp: CommonResponse = self.client().post(&self.url("/config"))
-            .json(&req).send().await?
-            .json().await?;
+        self.call(&Request { config: new_config }).await?;
 
-        assert(resp.code, "ModifyConfig")
+        Ok(())
     }
 }
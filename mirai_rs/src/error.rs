@@ -1,17 +1,29 @@
 use std::error::{Error};
 
 use reqwest::Error as ReqError;
+use serde_json::Error as JsonError;
+use url::ParseError;
 
 use std::fmt::Formatter;
 
-use crate::Code;
+use crate::{Code, Target};
+use crate::secret::redact_query;
 
 pub type HttpResult<T> = std::result::Result<T, HttpError>;
 
-#[derive(Debug)]
 pub enum HttpError {
     Reqwest(ReqError),
+    Json(JsonError),
     StatusCode(StatusCodeError),
+    /// No recorded exchange matches the request, see [`Replayer`](crate::transport::record::Replayer).
+    Replay(String),
+    Url(UrlError),
+    /// An argument is rejected before sending the request, such as a too long mute duration.
+    InvalidArgument(String),
+    /// The bot is paused by a [`BotManager`](crate::manager::BotManager), the message is not sent.
+    BotPaused(Target),
+    /// The server doesn't report something, such as the mute state which is only received from mirai-api-http 2.x.
+    Unsupported(String),
 }
 
 impl From<ReqError> for HttpError {
@@ -20,6 +32,18 @@ impl From<ReqError> for HttpError {
     }
 }
 
+impl From<JsonError> for HttpError {
+    fn from(e: JsonError) -> Self {
+        HttpError::Json(e)
+    }
+}
+
+impl From<UrlError> for HttpError {
+    fn from(e: UrlError) -> Self {
+        HttpError::Url(e)
+    }
+}
+
 impl From<StatusCodeError> for HttpError {
     fn from(e: StatusCodeError) -> Self {
         HttpError::StatusCode(e)
@@ -29,8 +53,30 @@ impl From<StatusCodeError> for HttpError {
 impl std::fmt::Display for HttpError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            HttpError::Reqwest(e) => { e.fmt(f) },
+            HttpError::Reqwest(e) => { f.write_str(&redact_query(&e.to_string())) },
+            HttpError::Json(e) => { e.fmt(f) },
             HttpError::StatusCode(e) => { e.fmt(f) },
+            HttpError::Replay(request) => { write!(f, "No recorded response for {}", request) },
+            HttpError::Url(e) => { e.fmt(f) },
+            HttpError::InvalidArgument(message) => { write!(f, "Invalid argument: {}", message) },
+            HttpError::BotPaused(bot) => { write!(f, "Bot {} is paused", bot) },
+            HttpError::Unsupported(message) => { write!(f, "Unsupported: {}", message) },
+        }
+    }
+}
+
+/// The url of a reqwest error contains the session key, so it is redacted.
+impl std::fmt::Debug for HttpError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HttpError::Reqwest(e) => f.debug_tuple("Reqwest").field(&format_args!("{}", redact_query(&format!("{:?}", e)))).finish(),
+            HttpError::Json(e) => f.debug_tuple("Json").field(e).finish(),
+            HttpError::StatusCode(e) => f.debug_tuple("StatusCode").field(e).finish(),
+            HttpError::Replay(request) => f.debug_tuple("Replay").field(request).finish(),
+            HttpError::Url(e) => f.debug_tuple("Url").field(e).finish(),
+            HttpError::InvalidArgument(message) => f.debug_tuple("InvalidArgument").field(message).finish(),
+            HttpError::BotPaused(bot) => f.debug_tuple("BotPaused").field(bot).finish(),
+            HttpError::Unsupported(message) => f.debug_tuple("Unsupported").field(message).finish(),
         }
     }
 }
@@ -43,6 +89,23 @@ pub struct StatusCodeError {
     action: String,
 }
 
+impl StatusCodeError {
+    pub(crate) fn new(code: Code, action: &str) -> StatusCodeError {
+        StatusCodeError {
+            code,
+            action: action.to_string(),
+        }
+    }
+
+    pub fn code(&self) -> Code {
+        self.code
+    }
+
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+}
+
 impl std::fmt::Display for StatusCodeError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let msg = match self.code {
@@ -67,25 +130,50 @@ impl std::fmt::Display for StatusCodeError {
 
 impl Error for StatusCodeError {}
 
-const SUCCESS: Code = 0;
-const WRONG_AUTH_KEY: Code = 1;
-const NO_SUCH_BOT: Code = 2;
-const WRONG_SESSION: Code = 3;
-const UNAUTHORIZED: Code = 4;
-const NO_SUCH_TARGET: Code = 5;
-const NO_SUCH_FILE: Code = 6;
-const PERMISSION_DENIED: Code = 10;
-const MUTED: Code = 20;
-const MESSAGE_TOO_LONG: Code = 30;
-const BAD_REQUEST: Code = 400;
+/// The base url of a connection is invalid, see [`MiraiConnection::new`](crate::session::MiraiConnection::new).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum UrlError {
+    Parse(ParseError),
+    /// Only `http` and `https` are supported.
+    UnsupportedScheme(String),
+    /// The base url has a query string or a fragment.
+    UnexpectedQuery,
+}
+
+impl From<ParseError> for UrlError {
+    fn from(e: ParseError) -> Self {
+        UrlError::Parse(e)
+    }
+}
+
+impl std::fmt::Display for UrlError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UrlError::Parse(e) => write!(f, "Invalid base url: {}", e),
+            UrlError::UnsupportedScheme(scheme) => write!(f, "Unsupported scheme of base url: {}", scheme),
+            UrlError::UnexpectedQuery => f.write_str("Base url can not have a query string or a fragment"),
+        }
+    }
+}
+
+impl Error for UrlError {}
+
+pub const SUCCESS: Code = 0;
+pub const WRONG_AUTH_KEY: Code = 1;
+pub const NO_SUCH_BOT: Code = 2;
+pub const WRONG_SESSION: Code = 3;
+pub const UNAUTHORIZED: Code = 4;
+pub const NO_SUCH_TARGET: Code = 5;
+pub const NO_SUCH_FILE: Code = 6;
+pub const PERMISSION_DENIED: Code = 10;
+pub const MUTED: Code = 20;
+pub const MESSAGE_TOO_LONG: Code = 30;
+pub const BAD_REQUEST: Code = 400;
 
 pub(crate) fn assert(code: Code, action: &str) -> HttpResult<()> {
     if code == SUCCESS {
         Ok(())
     } else {
-        Err(StatusCodeError {
-            code,
-            action: action.to_string(),
-        }.into())
+        Err(StatusCodeError::new(code, action).into())
     }
 }
\ No newline at end of file
@@ -1,12 +1,22 @@
-use serde::{Serialize, Deserialize};
+use std::time::Duration;
 
-use crate::session::{Session, CommonResponse};
-use crate::error::{HttpResult, assert};
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+
+use crate::api::{ApiRequest, parse_data};
+use crate::session::{Session, CommonResponse, ProtocolVersion};
+use crate::error::{HttpError, HttpResult, StatusCodeError, NO_SUCH_TARGET, PERMISSION_DENIED};
+use crate::message::{MessageID, TimeStamp};
+use crate::message::element::{Group, Permission};
+use crate::message::event::{ChangeType, GroupChangeEvent, GroupChangeEventKind};
+use crate::transport::Method;
 use crate::Target;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct GroupConfig {
     pub name: String,
+    /// The announcement is not in the group config of mirai-api-http 2.x, it is empty there.
+    #[serde(default)]
     pub announcement: String,
     #[serde(rename = "confessTalk")]
     pub confess_talk: bool,
@@ -16,36 +26,487 @@ pub struct GroupConfig {
     pub auto_approve: bool,
     #[serde(rename = "anonymousChat")]
     pub anonymous_chat: bool,
+    /// Whether all the members are muted, it is only received from mirai-api-http 2.x, and is `None` otherwise.
+    /// It is never sent when modifying the config, use [`Session::mute_all`] and [`Session::unmute_all`] instead.
+    #[serde(rename = "muteAll", default, skip_serializing)]
+    pub mute_all: Option<bool>,
+}
+
+/// A change of the [`GroupConfig`], only the fields which are set are modified.
+///
+/// ```ignore
+/// session.patch_group_config(group_id, &GroupConfigPatch::new().name("New Name")).await?;
+/// ```
+#[serde(rename_all = "camelCase")]
+#[derive(Serialize, Clone, Debug, Default, Eq, PartialEq)]
+pub struct GroupConfigPatch {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) announcement: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) confess_talk: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) allow_member_invite: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) auto_approve: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) anonymous_chat: Option<bool>,
+}
+
+impl GroupConfigPatch {
+    pub fn new() -> GroupConfigPatch {
+        GroupConfigPatch::default()
+    }
+
+    /// Return a patch which makes the [old] config the [new] one, see [`diff`].
+    /// The mute-all state is not in patches, it is ignored.
+    pub fn from_diff(old: &GroupConfig, new: &GroupConfig) -> GroupConfigPatch {
+        diff(old, new).iter().fold(GroupConfigPatch::new(), |patch, change| {
+            match (change.field, &change.change) {
+                (GroupConfigField::Name, ChangeType::String { current, .. }) => patch.name(current),
+                (GroupConfigField::Announcement, ChangeType::String { current, .. }) => patch.announcement(current),
+                (GroupConfigField::ConfessTalk, ChangeType::Bool { current, .. }) => patch.confess_talk(*current),
+                (GroupConfigField::AllowMemberInvite, ChangeType::Bool { current, .. }) => patch.allow_member_invite(*current),
+                (GroupConfigField::AutoApprove, ChangeType::Bool { current, .. }) => patch.auto_approve(*current),
+                (GroupConfigField::AnonymousChat, ChangeType::Bool { current, .. }) => patch.anonymous_chat(*current),
+                _ => patch,
+            }
+        })
+    }
+
+    pub fn name<S: AsRef<str>>(mut self, name: S) -> Self {
+        self.name = Some(name.as_ref().to_string());
+        self
+    }
+
+    /// Set the entrance announcement, it is not supported by mirai-api-http 2.x, use [`Session::publish_announcement`] instead.
+    pub fn announcement<S: AsRef<str>>(mut self, announcement: S) -> Self {
+        self.announcement = Some(announcement.as_ref().to_string());
+        self
+    }
+
+    pub fn confess_talk(mut self, confess_talk: bool) -> Self {
+        self.confess_talk = Some(confess_talk);
+        self
+    }
+
+    pub fn allow_member_invite(mut self, allow_member_invite: bool) -> Self {
+        self.allow_member_invite = Some(allow_member_invite);
+        self
+    }
+
+    pub fn auto_approve(mut self, auto_approve: bool) -> Self {
+        self.auto_approve = Some(auto_approve);
+        self
+    }
+
+    pub fn anonymous_chat(mut self, anonymous_chat: bool) -> Self {
+        self.anonymous_chat = Some(anonymous_chat);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == GroupConfigPatch::default()
+    }
+
+    /// Modify the [config] like the server does.
+    pub fn apply(&self, config: &mut GroupConfig) {
+        fn set<T: Clone>(field: &mut T, value: &Option<T>) {
+            if let Some(value) = value {
+                *field = value.clone();
+            }
+        }
+
+        set(&mut config.name, &self.name);
+        set(&mut config.announcement, &self.announcement);
+        set(&mut config.confess_talk, &self.confess_talk);
+        set(&mut config.allow_member_invite, &self.allow_member_invite);
+        set(&mut config.auto_approve, &self.auto_approve);
+        set(&mut config.anonymous_chat, &self.anonymous_chat);
+    }
+}
+
+/// The fields of [`GroupConfig`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum GroupConfigField {
+    Name,
+    Announcement,
+    ConfessTalk,
+    AllowMemberInvite,
+    AutoApprove,
+    AnonymousChat,
+    MuteAll,
+}
+
+impl GroupConfigField {
+    /// Return the field which is changed by the events of [kind].
+    pub fn of_event(kind: GroupChangeEventKind) -> GroupConfigField {
+        match kind {
+            GroupChangeEventKind::GroupNameChangeEvent => GroupConfigField::Name,
+            GroupChangeEventKind::GroupEntranceAnnouncementChangeEvent => GroupConfigField::Announcement,
+            GroupChangeEventKind::GroupMuteAllEvent => GroupConfigField::MuteAll,
+            GroupChangeEventKind::GroupAllowAnonymousChatEvent => GroupConfigField::AnonymousChat,
+            GroupChangeEventKind::GroupAllowConfessTalkEvent => GroupConfigField::ConfessTalk,
+            GroupChangeEventKind::GroupAllowMemberInviteEvent => GroupConfigField::AllowMemberInvite,
+        }
+    }
+}
+
+/// A change of a field of [`GroupConfig`], the [`ChangeType`] is the same as the one of [`GroupChangeEvent`],
+/// so the changes which a bot made can be correlated with the events which it receives.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct GroupConfigChange {
+    pub(crate) field: GroupConfigField,
+    pub(crate) change: ChangeType,
+}
+
+impl GroupConfigChange {
+    pub fn new(field: GroupConfigField, change: ChangeType) -> GroupConfigChange {
+        GroupConfigChange { field, change }
+    }
+
+    pub fn field(&self) -> GroupConfigField {
+        self.field
+    }
+
+    pub fn change(&self) -> &ChangeType {
+        &self.change
+    }
+
+    /// Return `true` if the [event] reports this change.
+    pub fn matches(&self, event: &GroupChangeEvent) -> bool {
+        GroupConfigChange::from(event) == *self
+    }
+
+    /// Modify the [config] with the current value of this change.
+    pub fn apply(&self, config: &mut GroupConfig) {
+        match (self.field, &self.change) {
+            (GroupConfigField::Name, ChangeType::String { current, .. }) => config.name = current.clone(),
+            (GroupConfigField::Announcement, ChangeType::String { current, .. }) => config.announcement = current.clone(),
+            (GroupConfigField::ConfessTalk, ChangeType::Bool { current, .. }) => config.confess_talk = *current,
+            (GroupConfigField::AllowMemberInvite, ChangeType::Bool { current, .. }) => config.allow_member_invite = *current,
+            (GroupConfigField::AutoApprove, ChangeType::Bool { current, .. }) => config.auto_approve = *current,
+            (GroupConfigField::AnonymousChat, ChangeType::Bool { current, .. }) => config.anonymous_chat = *current,
+            (GroupConfigField::MuteAll, ChangeType::Bool { current, .. }) => config.mute_all = Some(*current),
+            _ => {}
+        }
+    }
+}
+
+impl From<&GroupChangeEvent> for GroupConfigChange {
+    fn from(event: &GroupChangeEvent) -> Self {
+        GroupConfigChange::new(GroupConfigField::of_event(event.kind()), event.change().clone())
+    }
+}
+
+/// Return the changes which make the [old] config the [new] one, in the order of the fields of [`GroupConfig`].
+pub fn diff(old: &GroupConfig, new: &GroupConfig) -> Vec<GroupConfigChange> {
+    let mut changes = Vec::new();
+
+    let strings = [
+        (GroupConfigField::Name, &old.name, &new.name),
+        (GroupConfigField::Announcement, &old.announcement, &new.announcement),
+    ];
+
+    for (field, origin, current) in strings.iter() {
+        if origin != current {
+            changes.push(GroupConfigChange::new(*field, ChangeType::String { origin: origin.to_string(), current: current.to_string() }));
+        }
+    }
+
+    let bools = [
+        (GroupConfigField::ConfessTalk, old.confess_talk, new.confess_talk),
+        (GroupConfigField::AllowMemberInvite, old.allow_member_invite, new.allow_member_invite),
+        (GroupConfigField::AutoApprove, old.auto_approve, new.auto_approve),
+        (GroupConfigField::AnonymousChat, old.anonymous_chat, new.anonymous_chat),
+    ];
+
+    for (field, origin, current) in bools.iter() {
+        if origin != current {
+            changes.push(GroupConfigChange::new(*field, ChangeType::Bool { origin: *origin, current: *current }));
+        }
+    }
+
+    // the mute-all state is only compared if both of the configs report it
+    if let (Some(origin), Some(current)) = (old.mute_all, new.mute_all) {
+        if origin != current {
+            changes.push(GroupConfigChange::new(GroupConfigField::MuteAll, ChangeType::Bool { origin, current }));
+        }
+    }
+
+    changes
+}
+
+/// The longest duration which a member can be muted for, it is 30 days.
+pub const MAX_MUTE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// An announcement of a group, it is only available in mirai-api-http 2.x.
+#[serde(rename_all = "camelCase")]
+#[derive(Deserialize, Clone, Debug)]
+pub struct Announcement {
+    pub(crate) group: Group,
+    pub(crate) content: String,
+    pub(crate) sender_id: Target,
+    /// The id of the announcement.
+    pub(crate) fid: String,
+    pub(crate) all_confirmed: bool,
+    pub(crate) confirmed_members_count: u32,
+    pub(crate) publication_time: TimeStamp,
+}
+
+impl Announcement {
+    pub fn group(&self) -> &Group {
+        &self.group
+    }
+
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    pub fn sender_id(&self) -> Target {
+        self.sender_id
+    }
+
+    /// The id of the announcement, which is used to delete it.
+    pub fn fid(&self) -> &str {
+        &self.fid
+    }
+
+    /// Whether all the members confirmed the announcement, if it requires confirmation.
+    pub fn all_confirmed(&self) -> bool {
+        self.all_confirmed
+    }
+
+    pub fn confirmed_members_count(&self) -> u32 {
+        self.confirmed_members_count
+    }
+
+    pub fn publication_time(&self) -> TimeStamp {
+        self.publication_time
+    }
+}
+
+/// An announcement to publish, see [`Session::publish_announcement`].
+///
+/// ```ignore
+/// let announcement = NewAnnouncement::new("Weekly rules: ...")
+///     .pinned(true)
+///     .show_popup(true);
+///
+/// session.publish_announcement(group_id, &announcement).await?;
+/// ```
+#[serde(rename_all = "camelCase")]
+#[derive(Serialize, Clone, Debug, Default, Eq, PartialEq)]
+pub struct NewAnnouncement {
+    pub(crate) content: String,
+    pub(crate) send_to_new_member: bool,
+    pub(crate) pinned: bool,
+    pub(crate) show_edit_card: bool,
+    pub(crate) show_popup: bool,
+    pub(crate) require_confirmation: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) image_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) image_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) image_base64: Option<String>,
+}
+
+impl NewAnnouncement {
+    pub fn new<S: AsRef<str>>(content: S) -> NewAnnouncement {
+        NewAnnouncement {
+            content: content.as_ref().to_string(),
+            ..NewAnnouncement::default()
+        }
+    }
+
+    /// Send the announcement to the members who join the group later.
+    pub fn send_to_new_member(mut self, send_to_new_member: bool) -> Self {
+        self.send_to_new_member = send_to_new_member;
+        self
+    }
+
+    pub fn pinned(mut self, pinned: bool) -> Self {
+        self.pinned = pinned;
+        self
+    }
+
+    /// Show a prompt which leads members to edit their name cards.
+    pub fn show_edit_card(mut self, show_edit_card: bool) -> Self {
+        self.show_edit_card = show_edit_card;
+        self
+    }
+
+    pub fn show_popup(mut self, show_popup: bool) -> Self {
+        self.show_popup = show_popup;
+        self
+    }
+
+    pub fn require_confirmation(mut self, require_confirmation: bool) -> Self {
+        self.require_confirmation = require_confirmation;
+        self
+    }
+
+    /// Attach the image at [url], an announcement has at most one image, so the image which was set before is replaced.
+    pub fn image_url<S: AsRef<str>>(mut self, url: S) -> Self {
+        self.clear_image();
+        self.image_url = Some(url.as_ref().to_string());
+        self
+    }
+
+    /// Attach the image at [path] on the server, see [`NewAnnouncement::image_url`].
+    pub fn image_path<S: AsRef<str>>(mut self, path: S) -> Self {
+        self.clear_image();
+        self.image_path = Some(path.as_ref().to_string());
+        self
+    }
+
+    /// Attach the image which is encoded in [base64], see [`NewAnnouncement::image_url`].
+    pub fn image_base64<S: AsRef<str>>(mut self, base64: S) -> Self {
+        self.clear_image();
+        self.image_base64 = Some(base64.as_ref().to_string());
+        self
+    }
+
+    fn clear_image(&mut self) {
+        self.image_url = None;
+        self.image_path = None;
+        self.image_base64 = None;
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct MemberInfo {
+    /// It is `memberName` when getting in mirai-api-http 2.x.
+    #[serde(alias = "memberName")]
     name: String,
     #[serde(rename = "specialTitle")]
     special_title: String,
+    /// In seconds, it is only received from mirai-api-http 2.x.
+    #[serde(rename = "muteTimeRemaining", default)]
+    mute_time_remaining: Option<u32>,
+}
+
+impl MemberInfo {
+    pub fn new<N: AsRef<str>, T: AsRef<str>>(name: N, special_title: T) -> MemberInfo {
+        MemberInfo {
+            name: name.as_ref().to_string(),
+            special_title: special_title.as_ref().to_string(),
+            mute_time_remaining: None,
+        }
+    }
+
+    /// The name card of the member.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn special_title(&self) -> &str {
+        &self.special_title
+    }
+
+    /// How long the member is still muted for, it is zero if the member is not muted.
+    /// It is `None` if the server doesn't report it, such as mirai-api-http 1.x.
+    pub fn mute_time_remaining(&self) -> Option<Duration> {
+        self.mute_time_remaining.map(|seconds| Duration::from_secs(seconds.into()))
+    }
+}
+
+/// A change of [`MemberInfo`], only the fields which are set are modified.
+///
+/// ```ignore
+/// session.modify_member_info(group_id, member_id, &MemberInfoChange::new().name("New Name Card")).await?;
+/// ```
+#[serde(rename_all = "camelCase")]
+#[derive(Serialize, Clone, Debug, Default, Eq, PartialEq)]
+pub struct MemberInfoChange {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) special_title: Option<String>,
+}
+
+impl MemberInfoChange {
+    pub fn new() -> MemberInfoChange {
+        MemberInfoChange::default()
+    }
+
+    /// Set the name card of the member.
+    pub fn name<S: AsRef<str>>(mut self, name: S) -> Self {
+        self.name = Some(name.as_ref().to_string());
+        self
+    }
+
+    /// Set the special title of the member, only the owner of the group can do it.
+    pub fn special_title<S: AsRef<str>>(mut self, special_title: S) -> Self {
+        self.special_title = Some(special_title.as_ref().to_string());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none() && self.special_title.is_none()
+    }
+}
+
+/// Change all the fields.
+impl From<MemberInfo> for MemberInfoChange {
+    fn from(info: MemberInfo) -> Self {
+        MemberInfoChange {
+            name: Some(info.name),
+            special_title: Some(info.special_title),
+        }
+    }
+}
+
+/// Fail before sending any request if the [field] is never reported by the protocol of the [session].
+fn require_v2(session: &Session, field: &str) -> HttpResult<()> {
+    match session.connection.protocol {
+        ProtocolVersion::V1 => Err(HttpError::Unsupported(format!("{} is only received from mirai-api-http 2.x", field))),
+        ProtocolVersion::V2 => Ok(()),
+    }
+}
+
+fn not_reported(field: &str) -> HttpError {
+    HttpError::Unsupported(format!("{} is not reported by the server", field))
 }
 
 impl Session {
-    async fn do_mute_all(&self, target: Target, mute: bool) -> HttpResult<()> {
-        let path = if mute { "muteAll" } else { "unmuteAll" };
+    /// Return the permission of the bound bot in the group, or `None` if the bot is not in the group.
+    pub async fn permission_in(&self, group_id: Target) -> HttpResult<Option<Permission>> {
+        let group = self.group_list().await?.into_iter().find(|group| group.id() == group_id);
+
+        Ok(group.map(|group| group.permission()))
+    }
 
+    /// Check that the bound bot has at least the [required] permission in the group before doing the [action],
+    /// it fails like the server does, with [`NO_SUCH_TARGET`] or [`PERMISSION_DENIED`].
+    pub(crate) async fn require_permission(&self, group_id: Target, required: Permission, action: &str) -> HttpResult<()> {
+        match self.permission_in(group_id).await? {
+            None => Err(StatusCodeError::new(NO_SUCH_TARGET, action).into()),
+            Some(permission) if permission < required => Err(StatusCodeError::new(PERMISSION_DENIED, action).into()),
+            Some(_) => Ok(()),
+        }
+    }
+
+    async fn do_mute_all(&self, target: Target, mute: bool) -> HttpResult<()> {
         #[derive(Serialize)]
         struct Request {
-            #[serde(rename = "sessionKey")]
-            session_key: String,
             target: Target,
+            #[serde(skip)]
+            mute: bool,
         }
 
-        let req = Request {
-            session_key: self.key.clone(),
-            target,
-        };
+        impl ApiRequest for Request {
+            type Response = CommonResponse;
+
+            fn path(&self) -> &str { if self.mute { "/muteAll" } else { "/unmuteAll" } }
+            fn action(&self) -> &str { if self.mute { "MuteAll" } else { "UnmuteAll" } }
+        }
 
-        let resp: CommonResponse = self.client().post(&(self.url("/") + path))
-            .json(&req).send().await?
-            .json().await?;
+        self.call(&Request { target, mute }).await?;
 
-        assert(resp.code, if mute { "MuteAll" } else { "UnmuteAll" })
+        Ok(())
     }
 
     pub async fn mute_all(&self, target: Target) -> HttpResult<()> {
@@ -56,159 +517,343 @@ impl Session {
         self.do_mute_all(target, false).await
     }
 
-    pub async fn mute(&self, group_id: Target, target: Target, seconds: u32) -> HttpResult<()> {
+    /// Return whether all the members of the group are muted, see [`GroupConfig::mute_all`].
+    /// It fails with [`HttpError::Unsupported`] if the server doesn't report it, such as mirai-api-http 1.x.
+    pub async fn is_mute_all(&self, group_id: Target) -> HttpResult<bool> {
+        require_v2(self, "muteAll")?;
+
+        self.get_group_config(group_id).await?.mute_all.ok_or_else(|| not_reported("muteAll"))
+    }
+
+    /// Mute the member [target] for [duration], which is rounded down to seconds.
+    /// It fails with [`HttpError::InvalidArgument`] if the duration is shorter than a second or longer than [`MAX_MUTE`].
+    pub async fn mute(&self, group_id: Target, target: Target, duration: Duration) -> HttpResult<()> {
+        #[serde(rename_all = "camelCase")]
         #[derive(Serialize)]
         struct Request {
-            #[serde(rename = "sessionKey")]
-            session_key: String,
             target: Target,
-            #[serde(rename = "memberId")]
             member_id: Target,
             time: u32,
         }
 
+        impl ApiRequest for Request {
+            type Response = CommonResponse;
+
+            fn path(&self) -> &str { "/mute" }
+            fn action(&self) -> &str { "Mute" }
+        }
+
+        if duration < Duration::from_secs(1) || duration > MAX_MUTE {
+            return Err(HttpError::InvalidArgument(format!("mute duration {:?} is not in 1s..={}s", duration, MAX_MUTE.as_secs())));
+        }
+
         let req = Request {
-            session_key: self.key.clone(),
             target: group_id,
             member_id: target,
-            time: seconds,
+            time: duration.as_secs() as u32,
         };
 
-        let resp: CommonResponse = self.client().post(&self.url("/mute"))
-            .json(&req).send().await?
-            .json().await?;
+        self.call(&req).await?;
 
-        assert(resp.code, "Mute")
+        Ok(())
     }
 
     pub async fn unmute(&self, group_id: Target, target: Target) -> HttpResult<()> {
+        #[serde(rename_all = "camelCase")]
         #[derive(Serialize)]
         struct Request {
-            #[serde(rename = "sessionKey")]
-            session_key: String,
             target: Target,
-            #[serde(rename = "memberId")]
             member_id: Target,
         }
 
+        impl ApiRequest for Request {
+            type Response = CommonResponse;
+
+            fn path(&self) -> &str { "/unmute" }
+            fn action(&self) -> &str { "Unmute" }
+        }
+
+        self.call(&Request { target: group_id, member_id: target }).await?;
+
+        Ok(())
+    }
+
+    pub async fn kick(&self, group_id: Target, target: Target, msg: &str) -> HttpResult<()> {
+        #[serde(rename_all = "camelCase")]
+        #[derive(Serialize)]
+        struct Request<'m> {
+            target: Target,
+            member_id: Target,
+            msg: &'m str,
+        }
+
+        impl ApiRequest for Request<'_> {
+            type Response = CommonResponse;
+
+            fn path(&self) -> &str { "/kick" }
+            fn action(&self) -> &str { "Kick" }
+        }
+
         let req = Request {
-            session_key: self.key.clone(),
             target: group_id,
             member_id: target,
+            msg,
         };
 
-        let resp: CommonResponse = self.client().post(&self.url("/unmute"))
-            .json(&req).send().await?
-            .json().await?;
+        self.call(&req).await?;
 
-        assert(resp.code, "Unmute")
+        Ok(())
     }
 
-    pub async fn kick(&self, group_id: Target, target: Target, msg: &str) -> HttpResult<()> {
+    /// Assign the member [target] as an administrator of the group, or unassign it if [assign] is `false`.
+    /// Only the owner of the group can do it, the permission of the bot is checked before sending.
+    pub async fn set_admin(&self, group_id: Target, target: Target, assign: bool) -> HttpResult<()> {
+        #[serde(rename_all = "camelCase")]
         #[derive(Serialize)]
         struct Request {
-            #[serde(rename = "sessionKey")]
-            session_key: String,
             target: Target,
-            #[serde(rename = "memberId")]
             member_id: Target,
-            msg: String,
+            assign: bool,
+        }
+
+        impl ApiRequest for Request {
+            type Response = CommonResponse;
+
+            fn path(&self) -> &str { "/memberAdmin" }
+            fn action(&self) -> &str { "MemberAdmin" }
         }
 
+        self.require_permission(group_id, Permission::Owner, "MemberAdmin").await?;
+
         let req = Request {
-            session_key: self.key.clone(),
             target: group_id,
             member_id: target,
-            msg: msg.to_string(),
+            assign,
         };
 
-        let resp: CommonResponse = self.client().post(&self.url("/kick"))
-            .json(&req).send().await?
-            .json().await?;
+        self.call(&req).await?;
+
+        Ok(())
+    }
+
+    /// Set the message [message_id] as an essence message of the group which it was sent to.
+    /// The group is not known before the call, so the server checks that the bot is an administrator of it.
+    pub async fn set_essence(&self, message_id: MessageID) -> HttpResult<()> {
+        #[derive(Serialize)]
+        struct Request {
+            target: MessageID,
+        }
+
+        impl ApiRequest for Request {
+            type Response = CommonResponse;
+
+            fn path(&self) -> &str { "/setEssence" }
+            fn action(&self) -> &str { "SetEssence" }
+        }
+
+        self.call(&Request { target: message_id }).await?;
 
-        assert(resp.code, "Kick")
+        Ok(())
     }
 
     pub async fn quit(&self, group_id: Target) -> HttpResult<()> {
         #[derive(Serialize)]
         struct Request {
-            #[serde(rename = "sessionKey")]
-            session_key: String,
             target: Target,
         }
 
-        let req = Request {
-            session_key: self.key.clone(),
-            target: group_id,
-        };
+        impl ApiRequest for Request {
+            type Response = CommonResponse;
 
-        let resp: CommonResponse = self.client().post(&self.url("/quit"))
-            .json(&req).send().await?
-            .json().await?;
+            fn path(&self) -> &str { "/quit" }
+            fn action(&self) -> &str { "Quit" }
+        }
+
+        self.call(&Request { target: group_id }).await?;
 
-        assert(resp.code, "Quit")
+        Ok(())
     }
 
     pub async fn modify_group_config(&self, group_id: Target, config: &GroupConfig) -> HttpResult<()> {
         #[derive(Serialize)]
-        struct Request {
-            #[serde(rename = "sessionKey")]
-            session_key: String,
+        struct Request<'c> {
             target: Target,
-            config: GroupConfig,
+            config: &'c GroupConfig,
         }
 
-        let req = Request {
-            session_key: self.key.clone(),
-            target: group_id,
-            config: config.clone(),
-        };
+        impl ApiRequest for Request<'_> {
+            type Response = CommonResponse;
+
+            fn path(&self) -> &str { "/groupConfig" }
+            fn action(&self) -> &str { "ModifyGroupConfig" }
+        }
 
-        let resp: CommonResponse = self.client().post(&self.url("/groupConfig"))
-            .json(&req).send().await?
-            .json().await?;
+        self.call(&Request { target: group_id, config }).await?;
 
-        assert(resp.code, "ModifyGroupConfig")
+        Ok(())
     }
 
-    pub async fn get_group_config(&self, group_id: Target) -> HttpResult<GroupConfig> {
-        let config: GroupConfig = self.client().get(&self.url(&format!("/groupConfig?sessionKey={}&target={}", self.key, group_id)))
-            .send().await?
-            .json().await?;
+    /// Modify only the fields of the group config which are set in the [patch], the other fields are kept.
+    pub async fn patch_group_config(&self, group_id: Target, patch: &GroupConfigPatch) -> HttpResult<()> {
+        #[derive(Serialize)]
+        struct Request<'p> {
+            target: Target,
+            config: &'p GroupConfigPatch,
+        }
+
+        impl ApiRequest for Request<'_> {
+            type Response = CommonResponse;
+
+            fn path(&self) -> &str { "/groupConfig" }
+            fn action(&self) -> &str { "ModifyGroupConfig" }
+        }
+
+        self.call(&Request { target: group_id, config: patch }).await?;
 
-        Ok(config)
+        Ok(())
     }
 
-    pub async fn modify_member_info(&self, group_id: Target, target: Target, info: &MemberInfo) -> HttpResult<()> {
+    pub async fn get_group_config(&self, group_id: Target) -> HttpResult<GroupConfig> {
         #[derive(Serialize)]
         struct Request {
-            #[serde(rename = "sessionKey")]
-            session_key: String,
             target: Target,
-            #[serde(rename = "memberId")]
+        }
+
+        impl ApiRequest for Request {
+            type Response = GroupConfig;
+
+            fn path(&self) -> &str { "/groupConfig" }
+            fn action(&self) -> &str { "GetGroupConfig" }
+            fn method(&self) -> Method { Method::Get }
+        }
+
+        self.call(&Request { target: group_id }).await
+    }
+
+    /// Modify the info of the member [target], only the fields which are set in the [change] are modified.
+    /// Setting the special title requires the bot to be the owner of the group, it is checked before sending.
+    pub async fn modify_member_info(&self, group_id: Target, target: Target, change: &MemberInfoChange) -> HttpResult<()> {
+        #[serde(rename_all = "camelCase")]
+        #[derive(Serialize)]
+        struct Request<'i> {
+            target: Target,
             member_id: Target,
-            info: MemberInfo,
+            info: &'i MemberInfoChange,
+        }
+
+        impl ApiRequest for Request<'_> {
+            type Response = CommonResponse;
+
+            fn path(&self) -> &str { "/memberInfo" }
+            fn action(&self) -> &str { "ModifyMemberInfo" }
+        }
+
+        if change.special_title.is_some() {
+            self.require_permission(group_id, Permission::Owner, "ModifyMemberInfo").await?;
         }
 
         let req = Request {
-            session_key: self.key.clone(),
             target: group_id,
             member_id: target,
-            info: info.clone(),
+            info: change,
         };
 
-        let resp: CommonResponse = self.client().post(&self.url("/groupConfig"))
-            .json(&req).send().await?
-            .json().await?;
+        self.call(&req).await?;
+
+        Ok(())
+    }
+
+    /// Return how long the member [target] is still muted for, see [`MemberInfo::mute_time_remaining`].
+    /// It fails with [`HttpError::Unsupported`] if the server doesn't report it, such as mirai-api-http 1.x.
+    pub async fn mute_time_remaining(&self, group_id: Target, target: Target) -> HttpResult<Duration> {
+        require_v2(self, "muteTimeRemaining")?;
 
-        assert(resp.code, "ModifyGroupConfig")
+        self.get_member_info(group_id, target).await?.mute_time_remaining().ok_or_else(|| not_reported("muteTimeRemaining"))
     }
 
     pub async fn get_member_info(&self, group_id: Target, target: Target) -> HttpResult<MemberInfo> {
-        let info: MemberInfo = self.client().get(&self.url(&format!("/memberInfo?sessionKey={}&target={}&memberId={}", self.key, group_id, target)))
-            .send().await?
-            .json().await?;
+        #[serde(rename_all = "camelCase")]
+        #[derive(Serialize)]
+        struct Request {
+            target: Target,
+            member_id: Target,
+        }
+
+        impl ApiRequest for Request {
+            type Response = MemberInfo;
 
-        Ok(info)
+            fn path(&self) -> &str { "/memberInfo" }
+            fn action(&self) -> &str { "GetMemberInfo" }
+            fn method(&self) -> Method { Method::Get }
+        }
+
+        self.call(&Request { target: group_id, member_id: target }).await
+    }
+
+    /// List the announcements of the group, [offset] and [size] are for paging.
+    pub async fn list_announcements(&self, group_id: Target, offset: usize, size: usize) -> HttpResult<Vec<Announcement>> {
+        #[derive(Serialize)]
+        struct Request {
+            id: Target,
+            offset: usize,
+            size: usize,
+        }
+
+        impl ApiRequest for Request {
+            type Response = Vec<Announcement>;
+
+            fn path(&self) -> &str { "/anno/list" }
+            fn action(&self) -> &str { "ListAnnouncements" }
+            fn method(&self) -> Method { Method::Get }
+
+            fn parse(response: Value) -> serde_json::Result<Vec<Announcement>> {
+                parse_data(response)
+            }
+        }
+
+        self.call(&Request { id: group_id, offset, size }).await
+    }
+
+    /// Publish the [announcement] in the group, and return the published one.
+    pub async fn publish_announcement(&self, group_id: Target, announcement: &NewAnnouncement) -> HttpResult<Announcement> {
+        #[derive(Serialize)]
+        struct Request<'a> {
+            target: Target,
+            #[serde(flatten)]
+            announcement: &'a NewAnnouncement,
+        }
+
+        impl ApiRequest for Request<'_> {
+            type Response = Announcement;
+
+            fn path(&self) -> &str { "/anno/publish" }
+            fn action(&self) -> &str { "PublishAnnouncement" }
+
+            fn parse(response: Value) -> serde_json::Result<Announcement> {
+                parse_data(response)
+            }
+        }
+
+        self.call(&Request { target: group_id, announcement }).await
+    }
+
+    /// Delete the announcement [fid] of the group, see [`Announcement::fid`].
+    pub async fn delete_announcement(&self, group_id: Target, fid: &str) -> HttpResult<()> {
+        #[derive(Serialize)]
+        struct Request<'f> {
+            id: Target,
+            fid: &'f str,
+        }
+
+        impl ApiRequest for Request<'_> {
+            type Response = CommonResponse;
+
+            fn path(&self) -> &str { "/anno/delete" }
+            fn action(&self) -> &str { "DeleteAnnouncement" }
+        }
+
+        self.call(&Request { id: group_id, fid }).await?;
+
+        Ok(())
     }
-}
\ No newline at end of file
+}
@@ -1,9 +1,9 @@
+use crate::api::ApiRequest;
 use crate::session::Session;
 use crate::error::HttpResult;
 use crate::message::MessageContent;
 
-use reqwest::multipart::{Form, Part};
-use reqwest::Body;
+use crate::transport::Field;
 
 use serde::{Serialize, Deserialize};
 use bytes::Bytes;
@@ -13,7 +13,9 @@ use bytes::Bytes;
 pub struct Image {
     pub image_id: String,
     pub url: String,
-    pub path: String,
+    /// The path of the image on the server, it is only returned by mirai-api-http 1.x.
+    #[serde(default)]
+    pub path: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -38,22 +40,34 @@ impl From<Image> for MessageContent {
         MessageContent::Image {
             image_id: Some(img.image_id),
             url: Some(img.url),
-            path: Some(img.path),
+            path: img.path,
         }
     }
 }
 
 impl Session {
     pub async fn upload_image(&self, image_type: ImageType, bytes: Bytes, file_name: String) -> HttpResult<Image> {
-        let form = Form::new()
-            .text("sessionKey", self.key.clone())
-            .text("type", image_type.to_string())
-            .part("img", Part::stream(Body::from(bytes)).file_name(file_name));
+        #[derive(Serialize)]
+        struct Request {
+            #[serde(rename = "type")]
+            image_type: String,
+            #[serde(skip)]
+            file_name: String,
+            #[serde(skip)]
+            bytes: Bytes,
+        }
+
+        impl ApiRequest for Request {
+            type Response = Image;
 
-        let img: Image = self.client().post(&self.url("/uploadImage"))
-            .multipart(form).send().await?
-            .json().await?;
+            fn path(&self) -> &str { "/uploadImage" }
+            fn action(&self) -> &str { "UploadImage" }
 
-        Ok(img)
+            fn files(&self) -> Vec<(String, Field)> {
+                vec![("img".to_string(), Field::File { file_name: self.file_name.clone(), bytes: self.bytes.clone() })]
+            }
+        }
+
+        self.call(&Request { image_type: image_type.to_string(), file_name, bytes }).await
     }
-}
\ No newline at end of file
+}
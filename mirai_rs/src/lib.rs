@@ -11,14 +11,26 @@
 #[macro_use]
 extern crate pest_derive;
 
+pub mod api;
+pub mod batch;
+pub mod cache;
 pub mod common;
 pub mod config;
 pub mod error;
 pub mod group;
 pub mod image;
+pub mod manager;
 pub mod member_list;
 pub mod message;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod secret;
 pub mod session;
+#[cfg(feature = "tracing")]
+mod trace;
+pub mod transport;
+#[cfg(feature = "webhook")]
+pub mod webhook;
 
 pub type Target = u64;
 pub type Code = u16;
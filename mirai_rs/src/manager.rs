@@ -0,0 +1,278 @@
+//! This mod provides a way to drive many bots which are logged in the same mirai-api-http server.
+//!
+//! # BotManager
+//!
+//! A [`BotManager`] owns one [`Session`] per bound bot, all of them are created from the same [`MiraiConnection`].
+//!
+//! ```ignore
+//! use mirai::manager::BotManager;
+//!
+//! let mut manager = BotManager::new(connection);
+//! manager.add_bot("auth_key", 123456789).await?;
+//! manager.add_bot("auth_key", 987654321).await?;
+//! ```
+//!
+//! [`BotManager::listen`] merges the events of every bot into one channel, each event is tagged with the bot which received it.
+//! You can reply through [`BotManager::send_message`], the message will be sent by the session of the given bot.
+//!
+//! When a bot goes offline (a [`BotLoginEvent`] is received), the manager pauses it,
+//! sending messages with a paused bot fails with [`HttpError::BotPaused`] without touching the server,
+//! and the bot will be resumed when it logs in again.
+//!
+//! The sessions are released by [`BotManager::remove_bot`] (or when they are replaced), and [`BotManager::shutdown`] releases all of them.
+//! Dropping the manager stops listening its bots, and their sessions are released in the background when they are dropped.
+//!
+//! [`BotLoginEvent`]: crate::message::event::BotLoginEvent
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+use tokio::sync::oneshot;
+use tokio::sync::mpsc::error::TryRecvError;
+
+use crate::Target;
+use crate::error::{HttpError, HttpResult, StatusCodeError, NO_SUCH_BOT};
+use crate::message::{EventPacket, Message, MessageChannel, MessageID};
+use crate::secret::SecretString;
+use crate::session::{MiraiConnection, Session};
+
+/// The count of events that a bot fetches each time when listening.
+const FETCH_COUNT: usize = 10;
+
+struct ManagedBot {
+    session: Arc<Session>,
+    online: Arc<AtomicBool>,
+    /// It is set when the bot is removed, the listening tasks of the bot stop at their next tick.
+    removed: Arc<AtomicBool>,
+}
+
+impl ManagedBot {
+    /// Stop the listening tasks and release the session.
+    async fn release(self) -> HttpResult<()> {
+        self.removed.store(true, Ordering::SeqCst);
+        self.session.release().await
+    }
+}
+
+impl Drop for ManagedBot {
+    fn drop(&mut self) {
+        self.removed.store(true, Ordering::SeqCst);
+    }
+}
+
+/// An event which is received by the bot [`bot`](Self::bot).
+#[derive(Debug, Clone)]
+pub struct BotEvent {
+    pub bot: Target,
+    pub packet: EventPacket,
+}
+
+/// An error which occurred when the bot [`bot`](Self::bot) was fetching events.
+#[derive(Debug)]
+pub struct BotError {
+    pub bot: Target,
+    pub error: HttpError,
+}
+
+/// The receiver of the events which are listened by [`BotManager::listen`] or [`Webhook::listen`],
+/// the listening stops when it is dropped.
+///
+/// [`Webhook::listen`]: crate::webhook::Webhook::listen
+#[derive(Debug)]
+pub struct BotEvents {
+    rx: UnboundedReceiver<Result<BotEvent, BotError>>,
+    closed: Arc<AtomicBool>,
+    /// It is dropped with the receiver, which stops the server of a webhook.
+    stop: Option<oneshot::Sender<()>>,
+}
+
+impl BotEvents {
+    /// Construct the receiver of the events which are pushed to a webhook, the server stops when [stop] is dropped.
+    #[cfg(feature = "webhook")]
+    pub(crate) fn webhook(rx: UnboundedReceiver<Result<BotEvent, BotError>>, stop: oneshot::Sender<()>) -> BotEvents {
+        BotEvents {
+            rx,
+            closed: Arc::new(AtomicBool::new(false)),
+            stop: Some(stop),
+        }
+    }
+
+    pub async fn recv(&mut self) -> Option<Result<BotEvent, BotError>> {
+        self.rx.recv().await
+    }
+
+    pub fn try_recv(&mut self) -> Result<Result<BotEvent, BotError>, TryRecvError> {
+        self.rx.try_recv()
+    }
+}
+
+impl Drop for BotEvents {
+    fn drop(&mut self) {
+        self.closed.store(true, Ordering::SeqCst);
+        drop(self.stop.take());
+    }
+}
+
+pub struct BotManager {
+    connection: MiraiConnection,
+    bots: HashMap<Target, ManagedBot>,
+}
+
+impl BotManager {
+    pub fn new(connection: MiraiConnection) -> BotManager {
+        BotManager {
+            connection,
+            bots: HashMap::new(),
+        }
+    }
+
+    pub fn connection(&self) -> &MiraiConnection {
+        &self.connection
+    }
+
+    /// Authorize a new session and bind it with the given bot.
+    /// If the bot was managed before, the old session will be replaced and released,
+    /// the new session is managed even if releasing the old one fails.
+    pub async fn add_bot<K: Into<SecretString>>(&mut self, auth_key: K, qq: Target) -> HttpResult<()> {
+        // the session is released when it is dropped if verifying fails
+        let mut session = self.connection.auth(auth_key).await?;
+        session.verify(qq).await?;
+
+        let replaced = self.bots.insert(qq, ManagedBot {
+            session: Arc::new(session),
+            online: Arc::new(AtomicBool::new(true)),
+            removed: Arc::new(AtomicBool::new(false)),
+        });
+
+        match replaced {
+            Some(bot) => bot.release().await,
+            None => Ok(())
+        }
+    }
+
+    /// Stop managing the given bot, stop listening it and release its session.
+    /// Returns `false` if the bot is not managed by this manager.
+    pub async fn remove_bot(&mut self, qq: Target) -> HttpResult<bool> {
+        match self.bots.remove(&qq) {
+            Some(bot) => {
+                bot.release().await?;
+                Ok(true)
+            }
+
+            None => Ok(false)
+        }
+    }
+
+    /// Stop listening all the bots and release their sessions, returns the first error of releasing.
+    pub async fn shutdown(mut self) -> HttpResult<()> {
+        let mut result = Ok(());
+
+        for (_, bot) in self.bots.drain() {
+            let released = bot.release().await;
+            result = result.and(released);
+        }
+
+        result
+    }
+
+    /// Return all the bots which are managed by this manager.
+    pub fn bots(&self) -> Vec<Target> {
+        self.bots.keys().copied().collect()
+    }
+
+    /// Return the session which is bound with the given bot.
+    pub fn session(&self, qq: Target) -> Option<&Session> {
+        self.bots.get(&qq).map(|bot| &*bot.session)
+    }
+
+    /// Return `false` if the bot is paused or not managed by this manager.
+    pub fn is_online(&self, qq: Target) -> bool {
+        match self.bots.get(&qq) {
+            Some(bot) => bot.online.load(Ordering::SeqCst),
+            None => false
+        }
+    }
+
+    /// Pause the given bot, the events of it will still be received, but messages can not be sent with it.
+    pub fn pause(&self, qq: Target) {
+        if let Some(bot) = self.bots.get(&qq) {
+            bot.online.store(false, Ordering::SeqCst);
+        }
+    }
+
+    pub fn resume(&self, qq: Target) {
+        if let Some(bot) = self.bots.get(&qq) {
+            bot.online.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// Send a [message] to the given [channel] by the given [bot], and returns a [MessageID]
+    ///
+    /// It fails with [`NO_SUCH_BOT`] if the bot is not managed, or [`HttpError::BotPaused`] if the bot is paused.
+    pub async fn send_message(&self, bot: Target, channel: MessageChannel, message: &Message) -> HttpResult<MessageID> {
+        match self.bots.get(&bot) {
+            Some(managed) if managed.online.load(Ordering::SeqCst) => {
+                managed.session.send_message(channel, message).await
+            }
+
+            Some(_) => Err(HttpError::BotPaused(bot)),
+            None => Err(StatusCodeError::new(NO_SUCH_BOT, "Sending").into())
+        }
+    }
+
+    /// Start fetching events for every bot which is managed now, and merge them into one channel.
+    /// Each bot fetches its events every [interval], bots which are added after calling this function will not be listened.
+    ///
+    /// The listening stops when the returned receiver is dropped, and the listening of a bot stops when it is removed.
+    pub fn listen(&self, interval: Duration) -> BotEvents {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let closed = Arc::new(AtomicBool::new(false));
+
+        for (&qq, bot) in self.bots.iter() {
+            let tx = tx.clone();
+            let session = bot.session.clone();
+            let online = bot.online.clone();
+            let removed = bot.removed.clone();
+            let closed = closed.clone();
+            let stopped = move || closed.load(Ordering::SeqCst) || removed.load(Ordering::SeqCst);
+
+            tokio::spawn(async move {
+                while !stopped() {
+                    let result = session.fetch_message(FETCH_COUNT).await;
+
+                    // the session may be released while fetching
+                    if stopped() {
+                        return;
+                    }
+
+                    match result {
+                        Ok(events) => {
+                            for packet in events {
+                                if let EventPacket::BotLoginEvent(login) = &packet {
+                                    online.store(login.kind().is_online(), Ordering::SeqCst);
+                                }
+
+                                if tx.send(Ok(BotEvent { bot: qq, packet })).is_err() {
+                                    return;
+                                }
+                            }
+                        }
+
+                        Err(error) => {
+                            if tx.send(Err(BotError { bot: qq, error })).is_err() {
+                                return;
+                            }
+                        }
+                    }
+
+                    tokio::time::delay_for(interval).await;
+                }
+            });
+        }
+
+        BotEvents { rx, closed, stop: None }
+    }
+}
@@ -1,35 +1,57 @@
 //! This mod only provides a way to get a list of member (or group)
 //!
+//! The lists are responded directly in mirai-api-http 1.x, but they are wrapped with a status code in 2.x.
 
+use crate::api::{ApiRequest, parse_data};
 use crate::session::Session;
 use crate::error::HttpResult;
+use serde::Serialize;
 use serde::de::DeserializeOwned;
+use serde_json::Value;
 use crate::message::element::{FriendMember, Group, GroupMember};
+use crate::transport::Method;
 use crate::Target;
 
+#[derive(Serialize)]
+struct ListRequest<D> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target: Option<Target>,
+    #[serde(skip)]
+    path: &'static str,
+    #[serde(skip)]
+    item: std::marker::PhantomData<D>,
+}
+
+impl<D: DeserializeOwned> ApiRequest for ListRequest<D> {
+    type Response = Vec<D>;
+
+    fn path(&self) -> &str { self.path }
+    fn action(&self) -> &str { "GetList" }
+    fn method(&self) -> Method { Method::Get }
+
+    fn parse(response: Value) -> serde_json::Result<Vec<D>> {
+        parse_data(response)
+    }
+}
+
 impl Session {
-    async fn get_list<D>(&self, path: String) -> HttpResult<Vec<D>> where
+    async fn get_list<D>(&self, path: &'static str, target: Option<Target>) -> HttpResult<Vec<D>> where
         D: DeserializeOwned {
-        let resp = self.client()
-            .get(&self.url(&path))
-            .send().await?
-            .json().await?;
-
-        Ok(resp)
+        self.call(&ListRequest { target, path, item: std::marker::PhantomData }).await
     }
 
     /// Get the friend list of the bound QQ
     pub async fn friend_list(&self) -> HttpResult<Vec<FriendMember>> {
-        self.get_list(format!("/friendList?sessionKey={}", self.key)).await
+        self.get_list("/friendList", None).await
     }
 
     /// Get the group list of the bound QQ
     pub async fn group_list(&self) -> HttpResult<Vec<Group>> {
-        self.get_list(format!("/groupList?sessionKey={}", self.key)).await
+        self.get_list("/groupList", None).await
     }
 
     /// Get
     pub async fn group_member_list(&self, target: Target) -> HttpResult<Vec<GroupMember>> {
-        self.get_list(format!("/memberList?sessionKey={}&target={}", self.key, target)).await
+        self.get_list("/memberList", Some(target)).await
     }
 }
\ No newline at end of file
@@ -72,7 +72,7 @@ pub enum ChannelKind {
 /// let tmp = MessageChannel::Temp { qq: 456, group: 123 };
 /// assert_eq!(Some((456, 123)), tmp.temp().ok());
 /// ```
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum MessageChannel {
     Friend(Target),
     Group(Target),
@@ -14,15 +14,24 @@
 //!                       [`url`] is a url that points to an image,
 //!                       [`path`] is a path that points to an image in the server.
 //!                       They also have priority, [`image_id`] > [`url`] > [`path`].
+//! * Dice | MarketFace: A dice with its [`value`] and a market (aka store) face.
+//! * MusicShare: A music card, [`kind`] is the source of the music, such as `NeteaseCloudMusic` or `QQMusic`.
+//! * File: A group file, it can only be received.
+//! * Forward: A message which contains some other messages, see [`forward`] mod for more information.
 //! * Xml | Json | App | Poke: These message are not very commonly used, you can see [this](https://github.com/mamoe/mirai-api-http/blob/master/MessageType.md) for more information.
+//!   Note that [`Poke`] is the legacy poke, nudges are received as [`NudgeEvent`] and sent by [`Session::send_nudge`].
+//! * Unknown: A message element which mirai-rs doesn't know, [`kind`] is its type name and [`raw`] is the whole element which is serialized back unchanged.
 
-use serde::{Serialize, Deserialize};
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Error;
+use serde_json::Value;
 
 use crate::Target;
+use crate::message::forward::ForwardNode;
 use serde::export::fmt::Display;
 use serde::export::Formatter;
 
-#[serde(tag = "type")]
+#[serde(remote = "Self", tag = "type")]
 #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
 pub enum MessageContent {
     Plain {
@@ -62,9 +71,75 @@ pub enum MessageContent {
     Poke {
         name: String
     },
+    Dice {
+        value: i32
+    },
+    #[serde(rename_all = "camelCase")]
+    MusicShare {
+        kind: String,
+        title: String,
+        summary: String,
+        jump_url: String,
+        picture_url: String,
+        music_url: String,
+        brief: String,
+    },
+    MarketFace {
+        id: i32,
+        name: String,
+    },
+    File {
+        id: String,
+        name: String,
+        size: u64,
+    },
+    #[serde(rename_all = "camelCase")]
+    Forward {
+        title: String,
+        brief: String,
+        summary: String,
+        node_list: Vec<ForwardNode>,
+    },
 
-    #[serde(other)]
-    Unsupported,
+    #[serde(skip)]
+    Unknown {
+        kind: String,
+        raw: Value,
+    },
+}
+
+/// The type names of all the [`MessageContent`] variants except [`MessageContent::Unknown`].
+const KINDS: &[&str] = &[
+    "Plain", "At", "AtAll", "Face", "Image", "FlashImage", "Xml", "Json", "App", "Poke",
+    "Dice", "MusicShare", "MarketFace", "File", "Forward",
+];
+
+impl<'de> Deserialize<'de> for MessageContent {
+    fn deserialize<D>(deserializer: D) -> Result<Self, <D as Deserializer<'de>>::Error> where
+        D: Deserializer<'de> {
+        let raw = Value::deserialize(deserializer)?;
+        let kind = raw.get("type")
+            .and_then(Value::as_str)
+            .ok_or_else(|| D::Error::missing_field("type"))?
+            .to_string();
+
+        if KINDS.contains(&kind.as_str()) {
+            MessageContent::deserialize(raw).map_err(D::Error::custom)
+        } else {
+            Ok(MessageContent::Unknown { kind, raw })
+        }
+    }
+}
+
+impl Serialize for MessageContent {
+    fn serialize<S>(&self, serializer: S) -> Result<<S as Serializer>::Ok, <S as Serializer>::Error> where
+        S: Serializer {
+        if let MessageContent::Unknown { raw, .. } = self {
+            raw.serialize(serializer)
+        } else {
+            MessageContent::serialize(self, serializer)
+        }
+    }
 }
 
 impl<S: AsRef<str>> From<S> for MessageContent {
@@ -86,7 +161,12 @@ impl Display for MessageContent {
             MessageContent::Json { json } => format!("[json:{}]", json),
             MessageContent::App { content } => format!("[app:{}]", content),
             MessageContent::Poke { name } => format!("[poke:{}]", name),
-            MessageContent::Unsupported => format!("{:?}", MessageContent::Unsupported),
+            MessageContent::Dice { value } => format!("[dice:{}]", value),
+            MessageContent::MusicShare { title, .. } => format!("[music:{}]", title),
+            MessageContent::MarketFace { name, .. } => format!("[mface:{}]", name),
+            MessageContent::File { name, .. } => format!("[file:{}]", name),
+            MessageContent::Forward { title, .. } => format!("[forward:{}]", title),
+            MessageContent::Unknown { kind, .. } => format!("[unknown:{}]", kind),
             MessageContent::AtAll => "[atall]".to_string(),
             MessageContent::Face { face_id, name } => {
                 let s = if let Some(id) = face_id {
@@ -103,4 +183,26 @@ impl Display for MessageContent {
 
         f.write_str(&s)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+pub(crate) mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// Return the variants in the error of deserializing an unknown variant, such as
+    /// "unknown variant `X`, expected one of `A`, `B`".
+    pub(crate) fn expected_variants(error: serde_json::Error) -> Vec<String> {
+        let error = error.to_string();
+        let expected = &error[error.find("expected").expect("not an unknown variant error")..];
+
+        expected.split('`').skip(1).step_by(2).map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_kinds() {
+        let error = MessageContent::deserialize(json!({ "type": "UnknownContent" })).err().unwrap();
+
+        assert_eq!(expected_variants(error), KINDS.iter().map(|kind| kind.to_string()).collect::<Vec<_>>());
+    }
+}
@@ -1,9 +1,15 @@
+use std::cmp::Ordering;
+
 use serde::Deserialize;
+#[cfg(any(test, feature = "test-util"))]
+use serde::Serialize;
 
 use crate::Target;
 use crate::message::MessageContent;
 
-#[derive(Deserialize, Debug, Clone)]
+/// The permission of a member in a group, permissions are ordered as `Member < Administrator < Owner`.
+#[cfg_attr(any(test, feature = "test-util"), derive(Serialize))]
+#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
 pub enum Permission {
     #[serde(rename = "ADMINISTRATOR")]
     Administrator,
@@ -15,6 +21,29 @@ pub enum Permission {
     Member,
 }
 
+impl Permission {
+    fn rank(&self) -> u8 {
+        match self {
+            Permission::Member => 0,
+            Permission::Administrator => 1,
+            Permission::Owner => 2,
+        }
+    }
+}
+
+impl PartialOrd for Permission {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Permission {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank().cmp(&other.rank())
+    }
+}
+
+#[cfg_attr(any(test, feature = "test-util"), derive(Serialize))]
 #[derive(Debug, Clone, Deserialize)]
 pub struct GroupMember {
     pub(crate) id: Target,
@@ -33,6 +62,16 @@ impl PartialEq for GroupMember {
 impl Eq for GroupMember {}
 
 impl GroupMember {
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn new<S: AsRef<str>>(id: Target, member_name: S, permission: Permission, group: Group) -> GroupMember {
+        GroupMember {
+            id,
+            member_name: member_name.as_ref().to_string(),
+            permission,
+            group,
+        }
+    }
+
     pub fn id(&self) -> Target {
         self.id
     }
@@ -51,12 +90,14 @@ impl GroupMember {
 }
 
 #[serde(untagged)]
+#[cfg_attr(any(test, feature = "test-util"), derive(Serialize))]
 #[derive(Debug, Clone, Deserialize, Eq, PartialEq)]
 pub enum Sender {
     Friend(FriendMember),
     Group(GroupMember),
 }
 
+#[cfg_attr(any(test, feature = "test-util"), derive(Serialize))]
 #[derive(Debug, Clone, Deserialize)]
 pub struct FriendMember {
     pub(crate) id: Target,
@@ -74,6 +115,15 @@ impl PartialEq for FriendMember {
 impl Eq for FriendMember {}
 
 impl FriendMember {
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn new<N: AsRef<str>, R: AsRef<str>>(id: Target, nick_name: N, remark: R) -> FriendMember {
+        FriendMember {
+            id,
+            nick_name: nick_name.as_ref().to_string(),
+            remark: remark.as_ref().to_string(),
+        }
+    }
+
     pub fn id(&self) -> Target {
         self.id
     }
@@ -87,6 +137,7 @@ impl FriendMember {
     }
 }
 
+#[cfg_attr(any(test, feature = "test-util"), derive(Serialize))]
 #[derive(Debug, Clone, Deserialize)]
 pub struct Group {
     pub(crate) id: Target,
@@ -103,6 +154,15 @@ impl PartialEq for Group {
 impl Eq for Group {}
 
 impl Group {
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn new<S: AsRef<str>>(id: Target, name: S, permission: Permission) -> Group {
+        Group {
+            id,
+            name: name.as_ref().to_string(),
+            permission,
+        }
+    }
+
     pub fn id(&self) -> Target {
         self.id
     }
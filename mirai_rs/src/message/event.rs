@@ -10,15 +10,24 @@
 //! * FriendMessage: the message from a friend, it just contains a sender ([`FriendMember`])
 //!
 //! Other event variant information can found in [this](https://github.com/mamoe/mirai-api-http/blob/master/EventType.md).
+//!
+//! Events which mirai-rs doesn't know are received as [`EventPacket::Unknown`], it carries the type name and the whole event.
+//! A known event which can't be parsed is still an error.
+//!
+//! With the `test-util` feature, all the events can be constructed and serialized, so that you can fabricate events in tests.
 
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde::de::Error;
+#[cfg(any(test, feature = "test-util"))]
+use serde::Serializer;
 use serde_json::Value;
 
 use crate::Target;
 use crate::message::{MessageChain, MessageID, TimeStamp};
-use crate::message::element::{GroupMember, FriendMember, Group};
+use crate::message::element::{GroupMember, FriendMember, Group, Permission};
 
 #[serde(tag = "type")]
+#[cfg_attr(any(test, feature = "test-util"), derive(Serialize))]
 #[derive(Debug, Clone, Deserialize)]
 pub enum MessageEvent {
     GroupMessage {
@@ -60,6 +69,7 @@ impl MessageEvent {
 }
 
 #[serde(tag = "type")]
+#[cfg_attr(any(test, feature = "test-util"), derive(Serialize))]
 #[derive(Clone, Debug, Deserialize)]
 pub enum RecallEvent {
     GroupRecallEvent {
@@ -81,6 +91,7 @@ pub enum RecallEvent {
     },
 }
 
+#[cfg_attr(any(test, feature = "test-util"), derive(Serialize))]
 #[derive(Clone, Debug, Deserialize)]
 pub enum BotLoginEventKind {
     BotOnlineEvent,
@@ -90,6 +101,19 @@ pub enum BotLoginEventKind {
     BotReloginEvent,
 }
 
+impl BotLoginEventKind {
+    /// Return `true` if the bot is online after this event.
+    pub fn is_online(&self) -> bool {
+        match self {
+            BotLoginEventKind::BotOnlineEvent | BotLoginEventKind::BotReloginEvent => true,
+            BotLoginEventKind::BotOfflineEventActive
+            | BotLoginEventKind::BotOfflineEventForce
+            | BotLoginEventKind::BotOfflineEventDropped => false,
+        }
+    }
+}
+
+#[cfg_attr(any(test, feature = "test-util"), derive(Serialize))]
 #[derive(Debug, Clone, Deserialize)]
 pub struct BotLoginEvent {
     #[serde(rename = "type")]
@@ -97,13 +121,32 @@ pub struct BotLoginEvent {
     qq: Target,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+impl BotLoginEvent {
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn new(kind: BotLoginEventKind, qq: Target) -> BotLoginEvent {
+        BotLoginEvent { kind, qq }
+    }
+
+    pub fn kind(&self) -> &BotLoginEventKind {
+        &self.kind
+    }
+
+    pub fn qq(&self) -> Target {
+        self.qq
+    }
+}
+
+#[cfg_attr(any(test, feature = "test-util"), derive(Serialize))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize)]
 pub enum BotGroupEventKind {
     BotJoinGroupEvent,
     BotLeaveEventActive,
     BotLeaveEventKick,
+    /// The group is disbanded by its owner, it is only received from mirai-api-http 2.x.
+    BotLeaveEventDisband,
 }
 
+#[cfg_attr(any(test, feature = "test-util"), derive(Serialize))]
 #[derive(Debug, Clone, Deserialize)]
 pub struct BotGroupEvent {
     #[serde(rename = "type")]
@@ -111,7 +154,104 @@ pub struct BotGroupEvent {
     group: Group,
 }
 
+impl BotGroupEvent {
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn new(kind: BotGroupEventKind, group: Group) -> BotGroupEvent {
+        BotGroupEvent { kind, group }
+    }
+
+    pub fn kind(&self) -> BotGroupEventKind {
+        self.kind
+    }
+
+    pub fn group(&self) -> &Group {
+        &self.group
+    }
+
+    /// Return `true` if the bot is in the group after this event.
+    pub fn is_join(&self) -> bool {
+        self.kind == BotGroupEventKind::BotJoinGroupEvent
+    }
+}
+
+/// The permission of the bot in the [group] is changed by the owner.
 #[serde(tag = "type")]
+#[cfg_attr(any(test, feature = "test-util"), derive(Serialize))]
+#[derive(Debug, Clone, Deserialize)]
+pub enum BotGroupPermissionChangeEvent {
+    BotGroupPermissionChangeEvent {
+        origin: Permission,
+        current: Permission,
+        group: Group,
+    },
+}
+
+impl BotGroupPermissionChangeEvent {
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn new(origin: Permission, current: Permission, group: Group) -> BotGroupPermissionChangeEvent {
+        BotGroupPermissionChangeEvent::BotGroupPermissionChangeEvent { origin, current, group }
+    }
+
+    pub fn origin(&self) -> &Permission {
+        match self {
+            BotGroupPermissionChangeEvent::BotGroupPermissionChangeEvent { origin, .. } => origin,
+        }
+    }
+
+    pub fn current(&self) -> &Permission {
+        match self {
+            BotGroupPermissionChangeEvent::BotGroupPermissionChangeEvent { current, .. } => current,
+        }
+    }
+
+    pub fn group(&self) -> &Group {
+        match self {
+            BotGroupPermissionChangeEvent::BotGroupPermissionChangeEvent { group, .. } => group,
+        }
+    }
+}
+
+/// The events about the members of a group, the [`GroupMember`] carries the group which the member is in.
+#[serde(tag = "type")]
+#[cfg_attr(any(test, feature = "test-util"), derive(Serialize))]
+#[derive(Debug, Clone, Deserialize)]
+pub enum MemberEvent {
+    MemberJoinEvent {
+        member: GroupMember,
+    },
+    MemberLeaveEventKick {
+        member: GroupMember,
+        operator: Option<GroupMember>,
+    },
+    MemberLeaveEventQuit {
+        member: GroupMember,
+    },
+    MemberCardChangeEvent {
+        origin: String,
+        current: String,
+        member: GroupMember,
+    },
+    MemberPermissionChangeEvent {
+        origin: Permission,
+        current: Permission,
+        member: GroupMember,
+    },
+}
+
+impl MemberEvent {
+    pub fn member(&self) -> &GroupMember {
+        match self {
+            MemberEvent::MemberJoinEvent { member }
+            | MemberEvent::MemberLeaveEventKick { member, .. }
+            | MemberEvent::MemberLeaveEventQuit { member }
+            | MemberEvent::MemberCardChangeEvent { member, .. }
+            | MemberEvent::MemberPermissionChangeEvent { member, .. } => member,
+        }
+    }
+}
+
+#[serde(tag = "type")]
+#[cfg_attr(any(test, feature = "test-util"), derive(Serialize))]
 #[derive(Clone, Debug, Deserialize)]
 pub enum BotMuteEvent {
     BotMuteEvent {
@@ -124,17 +264,20 @@ pub enum BotMuteEvent {
     },
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(any(test, feature = "test-util"), derive(Serialize))]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize)]
 pub enum GroupChangeEventKind {
     GroupNameChangeEvent,
     GroupEntranceAnnouncementChangeEvent,
     GroupMuteAllEvent,
     GroupAllowAnonymousChatEvent,
+    GroupAllowConfessTalkEvent,
     GroupAllowMemberInviteEvent,
 }
 
 #[serde(untagged)]
-#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(any(test, feature = "test-util"), derive(Serialize))]
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize)]
 pub enum ChangeType {
     String {
         origin: String,
@@ -146,6 +289,7 @@ pub enum ChangeType {
     },
 }
 
+#[cfg_attr(any(test, feature = "test-util"), derive(Serialize))]
 #[derive(Clone, Debug, Deserialize)]
 pub struct GroupChangeEvent {
     #[serde(rename = "type")]
@@ -156,22 +300,239 @@ pub struct GroupChangeEvent {
     operator: Option<GroupMember>,
 }
 
-#[serde(untagged)]
+impl GroupChangeEvent {
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn new(kind: GroupChangeEventKind, change: ChangeType, group: Group, operator: Option<GroupMember>) -> GroupChangeEvent {
+        GroupChangeEvent {
+            kind,
+            change,
+            group,
+            operator,
+        }
+    }
+
+    pub fn kind(&self) -> GroupChangeEventKind {
+        self.kind
+    }
+
+    pub fn change(&self) -> &ChangeType {
+        &self.change
+    }
+
+    pub fn group(&self) -> &Group {
+        &self.group
+    }
+
+    /// The member who changed the group, it is `None` if the bot did it.
+    pub fn operator(&self) -> Option<&GroupMember> {
+        self.operator.as_ref()
+    }
+}
+
+/// The kind of the subject which a nudge happened in.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+pub enum NudgeKind {
+    Friend,
+    Group,
+    Stranger,
+}
+
+#[cfg_attr(any(test, feature = "test-util"), derive(Serialize))]
+#[derive(Debug, Clone, Deserialize)]
+pub struct NudgeSubject {
+    pub(crate) id: Target,
+    pub(crate) kind: NudgeKind,
+}
+
+impl NudgeSubject {
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn new(id: Target, kind: NudgeKind) -> NudgeSubject {
+        NudgeSubject { id, kind }
+    }
+
+    /// Return the id of the friend or the group which the nudge happened in.
+    pub fn id(&self) -> Target {
+        self.id
+    }
+
+    pub fn kind(&self) -> NudgeKind {
+        self.kind
+    }
+}
+
+#[serde(tag = "type")]
+#[cfg_attr(any(test, feature = "test-util"), derive(Serialize))]
+#[derive(Debug, Clone, Deserialize)]
+pub enum NudgeEvent {
+    #[serde(rename_all = "camelCase")]
+    NudgeEvent {
+        from_id: Target,
+        subject: NudgeSubject,
+        action: String,
+        suffix: String,
+        target: Target,
+    },
+}
+
+impl NudgeEvent {
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn new<A: AsRef<str>, S: AsRef<str>>(from_id: Target, subject: NudgeSubject, action: A, suffix: S, target: Target) -> NudgeEvent {
+        NudgeEvent::NudgeEvent {
+            from_id,
+            subject,
+            action: action.as_ref().to_string(),
+            suffix: suffix.as_ref().to_string(),
+            target,
+        }
+    }
+
+    /// Return the one who nudged.
+    pub fn from_id(&self) -> Target {
+        match self {
+            NudgeEvent::NudgeEvent { from_id, .. } => *from_id,
+        }
+    }
+
+    /// Return the one who was nudged.
+    pub fn target(&self) -> Target {
+        match self {
+            NudgeEvent::NudgeEvent { target, .. } => *target,
+        }
+    }
+
+    pub fn subject(&self) -> &NudgeSubject {
+        match self {
+            NudgeEvent::NudgeEvent { subject, .. } => subject,
+        }
+    }
+}
+
+#[serde(remote = "Self", untagged)]
+#[cfg_attr(any(test, feature = "test-util"), derive(Serialize))]
 #[derive(Debug, Clone, Deserialize)]
 pub enum EventPacket {
     MessageEvent(MessageEvent),
     BotLoginEvent(BotLoginEvent),
     BotMuteEvent(BotMuteEvent),
+    BotGroupEvent(BotGroupEvent),
+    BotGroupPermissionChangeEvent(BotGroupPermissionChangeEvent),
     RecallEvent(RecallEvent),
     GroupChangeEvent(GroupChangeEvent),
-    Unsupported(Value),
+    MemberEvent(MemberEvent),
+    NudgeEvent(NudgeEvent),
+    #[serde(skip)]
+    Unknown {
+        kind: String,
+        raw: Value,
+    },
+}
+
+/// The type names of all the events which [`EventPacket`] knows, the other events are [`EventPacket::Unknown`].
+const KINDS: &[&str] = &[
+    "GroupMessage", "FriendMessage",
+    "BotOnlineEvent", "BotOfflineEventActive", "BotOfflineEventForce", "BotOfflineEventDropped", "BotReloginEvent",
+    "BotMuteEvent", "BotUnmuteEvent",
+    "BotJoinGroupEvent", "BotLeaveEventActive", "BotLeaveEventKick", "BotLeaveEventDisband",
+    "BotGroupPermissionChangeEvent",
+    "GroupRecallEvent", "FriendRecallEvent",
+    "GroupNameChangeEvent", "GroupEntranceAnnouncementChangeEvent", "GroupMuteAllEvent",
+    "GroupAllowAnonymousChatEvent", "GroupAllowConfessTalkEvent", "GroupAllowMemberInviteEvent",
+    "MemberJoinEvent", "MemberLeaveEventKick", "MemberLeaveEventQuit", "MemberCardChangeEvent", "MemberPermissionChangeEvent",
+    "NudgeEvent",
+];
+
+impl<'de> Deserialize<'de> for EventPacket {
+    fn deserialize<D>(deserializer: D) -> Result<Self, <D as Deserializer<'de>>::Error> where
+        D: Deserializer<'de> {
+        let raw = Value::deserialize(deserializer)?;
+        let kind = raw.get("type")
+            .and_then(Value::as_str)
+            .ok_or_else(|| D::Error::missing_field("type"))?
+            .to_string();
+
+        if KINDS.contains(&kind.as_str()) {
+            EventPacket::deserialize(&raw).map_err(D::Error::custom)
+        } else {
+            Ok(EventPacket::Unknown { kind, raw })
+        }
+    }
+}
+
+/// [`EventPacket::Unknown`] is serialized as its [`raw`] event.
+#[cfg(any(test, feature = "test-util"))]
+impl Serialize for EventPacket {
+    fn serialize<S>(&self, serializer: S) -> Result<<S as Serializer>::Ok, <S as Serializer>::Error> where
+        S: Serializer {
+        if let EventPacket::Unknown { raw, .. } = self {
+            raw.serialize(serializer)
+        } else {
+            EventPacket::serialize(self, serializer)
+        }
+    }
 }
 
 impl EventPacket {
+    /// Return the name of the variant, or the type name of the event if it is [`EventPacket::Unknown`].
+    pub fn name(&self) -> &str {
+        match self {
+            EventPacket::MessageEvent(_) => "MessageEvent",
+            EventPacket::BotLoginEvent(_) => "BotLoginEvent",
+            EventPacket::BotMuteEvent(_) => "BotMuteEvent",
+            EventPacket::BotGroupEvent(_) => "BotGroupEvent",
+            EventPacket::BotGroupPermissionChangeEvent(_) => "BotGroupPermissionChangeEvent",
+            EventPacket::RecallEvent(_) => "RecallEvent",
+            EventPacket::GroupChangeEvent(_) => "GroupChangeEvent",
+            EventPacket::MemberEvent(_) => "MemberEvent",
+            EventPacket::NudgeEvent(_) => "NudgeEvent",
+            EventPacket::Unknown { kind, .. } => kind,
+        }
+    }
+
     pub fn is_message(&self) -> bool {
         match self {
             EventPacket::MessageEvent(_) => true,
             _ => false
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use serde::de::DeserializeOwned;
+    use serde_json::json;
+
+    use crate::message::content::tests::expected_variants;
+    use super::*;
+
+    /// Return the type names which [T] accepts, from the error of deserializing an unknown type.
+    fn kinds<T: DeserializeOwned>(unknown: Value) -> Vec<String> {
+        expected_variants(serde_json::from_value::<T>(unknown).err().unwrap())
+    }
+
+    #[test]
+    fn test_kinds() {
+        let tagged = json!({ "type": "UnknownEvent" });
+        let name = json!("UnknownEvent");
+
+        let mut expected = Vec::new();
+        expected.extend(kinds::<MessageEvent>(tagged.clone()));
+        expected.extend(kinds::<BotLoginEventKind>(name.clone()));
+        expected.extend(kinds::<BotMuteEvent>(tagged.clone()));
+        expected.extend(kinds::<BotGroupEventKind>(name.clone()));
+        expected.extend(kinds::<BotGroupPermissionChangeEvent>(tagged.clone()));
+        expected.extend(kinds::<RecallEvent>(tagged.clone()));
+        expected.extend(kinds::<GroupChangeEventKind>(name));
+        expected.extend(kinds::<MemberEvent>(tagged.clone()));
+        expected.extend(kinds::<NudgeEvent>(tagged));
+
+        assert_eq!(expected, KINDS.iter().map(|kind| kind.to_string()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_malformed_event() {
+        let unknown: EventPacket = serde_json::from_value(json!({ "type": "UnknownEvent", "qq": 1 })).unwrap();
+        assert_eq!("UnknownEvent", unknown.name());
+
+        assert!(serde_json::from_value::<EventPacket>(json!({ "type": "BotOnlineEvent" })).is_err());
+        assert!(serde_json::from_value::<EventPacket>(json!({ "qq": 1 })).is_err());
+    }
+}
@@ -0,0 +1,96 @@
+//! Forward (aka merged) message is a message which contains some other messages.
+//!
+//! # Forward
+//!
+//! A [`MessageContent::Forward`] contains a title, a brief, a summary and a list of [`ForwardNode`],
+//! each node is a message which was sent by somebody at some time.
+//!
+//! You can use [`ForwardBuilder`] to construct a forward message:
+//!
+//! ```rust
+//! use mirai::message::forward::ForwardBuilder;
+//!
+//! let forward = ForwardBuilder::new()
+//!     .title("Digest")
+//!     .node(123456789, "Alice", 1600000000, vec!["Hello".into()])
+//!     .node(987654321, "Bob", 1600000001, vec!["Hi".into()])
+//!     .build();
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::Target;
+use crate::message::{MessageChain, MessageContent, TimeStamp};
+
+#[serde(rename_all = "camelCase")]
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct ForwardNode {
+    pub sender_id: Target,
+    pub time: TimeStamp,
+    pub sender_name: String,
+    pub message_chain: MessageChain,
+}
+
+impl ForwardNode {
+    pub fn new<S: AsRef<str>, I: Into<MessageChain>>(sender_id: Target, sender_name: S, time: TimeStamp, message_chain: I) -> ForwardNode {
+        ForwardNode {
+            sender_id,
+            time,
+            sender_name: sender_name.as_ref().to_string(),
+            message_chain: message_chain.into(),
+        }
+    }
+}
+
+/// A builder of [`MessageContent::Forward`].
+///
+/// The [`title`], [`brief`] and [`summary`] have default values which are the same as the QQ client,
+/// the default [`summary`] is computed from the count of nodes when building.
+#[derive(Clone, Debug, Default)]
+pub struct ForwardBuilder {
+    title: Option<String>,
+    brief: Option<String>,
+    summary: Option<String>,
+    node_list: Vec<ForwardNode>,
+}
+
+impl ForwardBuilder {
+    pub fn new() -> ForwardBuilder {
+        ForwardBuilder::default()
+    }
+
+    pub fn title<S: AsRef<str>>(mut self, title: S) -> Self {
+        self.title = Some(title.as_ref().to_string());
+        self
+    }
+
+    pub fn brief<S: AsRef<str>>(mut self, brief: S) -> Self {
+        self.brief = Some(brief.as_ref().to_string());
+        self
+    }
+
+    pub fn summary<S: AsRef<str>>(mut self, summary: S) -> Self {
+        self.summary = Some(summary.as_ref().to_string());
+        self
+    }
+
+    pub fn append_node(mut self, node: ForwardNode) -> Self {
+        self.node_list.push(node);
+        self
+    }
+
+    pub fn node<S: AsRef<str>, I: Into<MessageChain>>(self, sender_id: Target, sender_name: S, time: TimeStamp, message_chain: I) -> Self {
+        self.append_node(ForwardNode::new(sender_id, sender_name, time, message_chain))
+    }
+
+    pub fn build(self) -> MessageContent {
+        let count = self.node_list.len();
+
+        MessageContent::Forward {
+            title: self.title.unwrap_or_else(|| "群聊的聊天记录".to_string()),
+            brief: self.brief.unwrap_or_else(|| "[聊天记录]".to_string()),
+            summary: self.summary.unwrap_or_else(|| format!("查看{}条转发消息", count)),
+            node_list: self.node_list,
+        }
+    }
+}
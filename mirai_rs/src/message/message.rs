@@ -3,7 +3,7 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde::de::{Visitor, SeqAccess};
 use std::fmt::Formatter;
 use serde::ser::SerializeSeq;
-use crate::message::meta_msg::{MessageSource, MessageMeta};
+use crate::message::meta_msg::{MessageSource, MessageMeta, Quote};
 
 pub type MessageChain = Vec<MessageContent>;
 pub type MessageID = i64;
@@ -35,7 +35,7 @@ impl SingleMessage {
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Message {
     pub(crate) source: MessageSource,
-    pub(crate) quote: Option<MessageID>,
+    pub(crate) quote: Option<Quote>,
     pub(crate) message_chain: MessageChain,
 }
 
@@ -52,7 +52,7 @@ impl Message {
     }
 
     pub fn quote(&mut self, quote: MessageID) {
-        self.quote = Some(quote);
+        self.quote = Some(Quote::new(quote));
     }
 
     pub fn source(&self) -> &MessageSource {
@@ -60,7 +60,12 @@ impl Message {
     }
 
     pub fn quoted(&self) -> Option<MessageID> {
-        self.quote
+        self.quote.as_ref().map(Quote::id)
+    }
+
+    /// Return the whole quote of this message, see [`Quote`].
+    pub fn quoted_message(&self) -> Option<&Quote> {
+        self.quote.as_ref()
     }
 
     pub fn message_chain(&self) -> &MessageChain {
@@ -100,8 +105,8 @@ impl<'de> Deserialize<'de> for Message {
                                 MessageMeta::Source(ms) => {
                                     source = Some(ms);
                                 }
-                                MessageMeta::Quote { id } => {
-                                    quote = Some(id);
+                                MessageMeta::Quote(mq) => {
+                                    quote = Some(mq);
                                 }
                             }
                         }
@@ -133,8 +138,8 @@ impl Serialize for Message {
 
         seq.serialize_element(&Some(SingleMessage::Meta(MessageMeta::Source(self.source.clone()))))?;
 
-        if let Some(quote) = self.quote {
-            let quote = SingleMessage::Meta(MessageMeta::Quote { id: quote });
+        if let Some(quote) = &self.quote {
+            let quote = SingleMessage::Meta(MessageMeta::Quote(quote.clone()));
             seq.serialize_element(&Some(quote))?;
         }
 
@@ -151,6 +156,7 @@ mod tests {
     use serde_json::*;
     use crate::message::{Message, MessageContent};
     use crate::message::message::MessageSource;
+    use crate::message::Quote;
 
     #[test]
     fn test_message_serde() {
@@ -180,7 +186,7 @@ mod tests {
                 id: -114514,
                 time: 1919810,
             },
-            quote: Some(19260817),
+            quote: Some(Quote::new(19260817)),
             message_chain: vec![
                 MessageContent::At {
                     target: 1005042620,
@@ -193,4 +199,220 @@ mod tests {
         assert_eq!(serde_json::from_value::<Message>(source.clone()).unwrap(), expect);
         assert_eq!(serde_json::to_value(expect.clone()).unwrap(), source);
     }
+
+    #[test]
+    fn test_forward_serde() {
+        use crate::message::ForwardBuilder;
+
+        let source = json! {[
+            {
+                "type": "Source",
+                "id": 114514,
+                "time": 1919810
+            },
+            {
+                "type": "Forward",
+                "title": "群聊的聊天记录",
+                "brief": "[聊天记录]",
+                "summary": "查看1条转发消息",
+                "nodeList": [
+                    {
+                        "senderId": 1005042620,
+                        "time": 1919810,
+                        "senderName": "星野",
+                        "messageChain": [
+                            {
+                                "type": "Plain",
+                                "text": "qwq"
+                            }
+                        ]
+                    }
+                ]
+            }
+        ]};
+
+        let expect = Message {
+            source: MessageSource {
+                id: 114514,
+                time: 1919810,
+            },
+            quote: None,
+            message_chain: vec![
+                ForwardBuilder::new()
+                    .node(1005042620, "星野", 1919810, vec!["qwq".into()])
+                    .build()
+            ],
+        };
+
+        assert_eq!(serde_json::from_value::<Message>(source.clone()).unwrap(), expect);
+        assert_eq!(serde_json::to_value(expect).unwrap(), source);
+    }
+
+    #[test]
+    fn test_unknown_serde() {
+        let voice = json! {{
+            "type": "Voice",
+            "voiceId": "23C4-1A2B",
+            "url": null,
+            "path": null
+        }};
+
+        let source = json! {[
+            {
+                "type": "Source",
+                "id": 114514,
+                "time": 1919810
+            },
+            voice.clone()
+        ]};
+
+        let message = serde_json::from_value::<Message>(source.clone()).unwrap();
+
+        assert_eq!(message.message_chain, vec![
+            MessageContent::Unknown {
+                kind: "Voice".to_string(),
+                raw: voice,
+            }
+        ]);
+        assert_eq!(serde_json::to_value(message).unwrap(), source);
+    }
+
+    #[test]
+    fn test_quote_serde() {
+        let source = json! {[
+            {
+                "type": "Source",
+                "id": 114514,
+                "time": 1919810
+            },
+            {
+                "type": "Quote",
+                "id": 19260817,
+                "groupId": 972342866,
+                "senderId": 1005042620,
+                "targetId": 972342866,
+                "origin": [
+                    {
+                        "type": "Dice",
+                        "value": 6
+                    }
+                ]
+            },
+            {
+                "type": "MarketFace",
+                "id": 233,
+                "name": "[喜欢]"
+            }
+        ]};
+
+        let message = serde_json::from_value::<Message>(source.clone()).unwrap();
+        let quote = message.quoted_message().unwrap();
+
+        assert_eq!(message.quoted(), Some(19260817));
+        assert_eq!(quote.sender_id(), Some(1005042620));
+        assert_eq!(quote.origin(), Some(&vec![MessageContent::Dice { value: 6 }]));
+        assert_eq!(message.message_chain, vec![
+            MessageContent::MarketFace {
+                id: 233,
+                name: "[喜欢]".to_string(),
+            }
+        ]);
+        assert_eq!(serde_json::to_value(message).unwrap(), source);
+    }
+
+    #[test]
+    fn test_nudge_serde() {
+        use crate::message::EventPacket;
+        use crate::message::event::{NudgeEvent, NudgeKind};
+
+        let nudge = |id, kind| json! {{
+            "type": "NudgeEvent",
+            "fromId": 1005042620,
+            "subject": {
+                "id": id,
+                "kind": kind
+            },
+            "action": "戳了戳",
+            "suffix": "的脸",
+            "target": 1234567890
+        }};
+
+        for (id, kind, expected) in &[(1005042620, "Friend", NudgeKind::Friend), (972342866, "Group", NudgeKind::Group)] {
+            match serde_json::from_value::<EventPacket>(nudge(id, kind)).unwrap() {
+                EventPacket::NudgeEvent(event) => {
+                    let NudgeEvent::NudgeEvent { action, suffix, .. } = &event;
+
+                    assert_eq!(1005042620, event.from_id());
+                    assert_eq!(1234567890, event.target());
+                    assert_eq!(*id, event.subject().id());
+                    assert_eq!(*expected, event.subject().kind());
+                    assert_eq!(("戳了戳", "的脸"), (action.as_str(), suffix.as_str()));
+                }
+
+                packet => panic!("unexpected packet: {:?}", packet),
+            }
+        }
+    }
+
+    #[test]
+    fn test_event_serde() {
+        use crate::message::EventPacket;
+        use crate::message::element::{FriendMember, Group, GroupMember, Permission};
+        use crate::message::event::MessageEvent;
+
+        let group = Group::new(972342866, "Mirai", Permission::Member);
+        let sender = GroupMember::new(1005042620, "Hoshino", Permission::Owner, group);
+        let packet = EventPacket::MessageEvent(MessageEvent::GroupMessage {
+            message_chain: vec!["/roll".into()],
+            sender,
+        });
+
+        let source = json! {{
+            "type": "GroupMessage",
+            "messageChain": [
+                {
+                    "type": "Plain",
+                    "text": "/roll"
+                }
+            ],
+            "sender": {
+                "id": 1005042620,
+                "memberName": "Hoshino",
+                "permission": "OWNER",
+                "group": {
+                    "id": 972342866,
+                    "name": "Mirai",
+                    "permission": "MEMBER"
+                }
+            }
+        }};
+
+        assert_eq!(serde_json::to_value(&packet).unwrap(), source);
+
+        let packet = serde_json::from_value::<EventPacket>(source.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&packet).unwrap(), source);
+
+        let friend = FriendMember::new(1005042620, "Hoshino", String::from("Tented"));
+        let packet = EventPacket::MessageEvent(MessageEvent::FriendMessage {
+            message_chain: vec!["/roll".into()],
+            sender: friend,
+        });
+
+        let source = json! {{
+            "type": "FriendMessage",
+            "messageChain": [
+                {
+                    "type": "Plain",
+                    "text": "/roll"
+                }
+            ],
+            "sender": {
+                "id": 1005042620,
+                "nickname": "Hoshino",
+                "remark": "Tented"
+            }
+        }};
+
+        assert_eq!(serde_json::to_value(&packet).unwrap(), source);
+    }
 }
\ No newline at end of file
@@ -1,4 +1,5 @@
-use crate::message::{MessageID, TimeStamp};
+use crate::Target;
+use crate::message::{MessageChain, MessageID, TimeStamp};
 
 use serde::{Deserialize, Serialize};
 
@@ -8,11 +9,61 @@ pub struct MessageSource {
     pub(crate) time: TimeStamp,
 }
 
+/// The quoted message of a message.
+///
+/// A received quote contains the whole information of the quoted message,
+/// but a quote which is constructed by [`Quote::new`] only contains the [`id`], it is enough to send a message.
+#[serde(rename_all = "camelCase")]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Quote {
+    pub(crate) id: MessageID,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) group_id: Option<Target>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) sender_id: Option<Target>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) target_id: Option<Target>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) origin: Option<MessageChain>,
+}
+
+impl Quote {
+    pub fn new(id: MessageID) -> Quote {
+        Quote {
+            id,
+            group_id: None,
+            sender_id: None,
+            target_id: None,
+            origin: None,
+        }
+    }
+
+    pub fn id(&self) -> MessageID {
+        self.id
+    }
+
+    /// Return the group which the quoted message was sent in, `Some(0)` if it is a friend message.
+    pub fn group_id(&self) -> Option<Target> {
+        self.group_id
+    }
+
+    pub fn sender_id(&self) -> Option<Target> {
+        self.sender_id
+    }
+
+    pub fn target_id(&self) -> Option<Target> {
+        self.target_id
+    }
+
+    /// Return the message chain of the quoted message.
+    pub fn origin(&self) -> Option<&MessageChain> {
+        self.origin.as_ref()
+    }
+}
+
 #[serde(tag = "type")]
 #[derive(Debug, Deserialize, Serialize)]
 pub enum MessageMeta {
     Source(MessageSource),
-    Quote {
-        id: MessageID
-    },
+    Quote(Quote),
 }
@@ -1,5 +1,6 @@
 pub mod event;
 pub mod content;
+pub mod forward;
 pub mod element;
 pub mod channel;
 pub mod send;
@@ -10,8 +11,10 @@ pub mod meta_msg;
 
 pub use channel::MessageChannel;
 pub use content::MessageContent;
+pub use forward::{ForwardNode, ForwardBuilder};
 pub use event::EventPacket;
 pub use element::{Group, GroupMember, FriendMember};
 pub use content::MessageContent::*;
 pub use meta_msg::{*, MessageMeta::*};
+pub use meta_msg::Quote;
 pub use message::{TimeStamp, MessageID, Message, MessageChain};
\ No newline at end of file
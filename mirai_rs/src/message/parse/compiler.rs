@@ -5,6 +5,7 @@ use pest::iterators::{Pair, Pairs};
 
 use crate::message::{Message, MessageContent, MessageID};
 use crate::message::message::MessageSource;
+use crate::message::meta_msg::Quote;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Index<'s> {
@@ -54,7 +55,7 @@ impl PatternComponent for MessageContent {
 
 impl PatternComponent for MessageID {
     fn apply_to(self, mut message: Message) {
-        message.quote = Some(self)
+        message.quote = Some(Quote::new(self))
     }
 
     fn clone(&self) -> Box<dyn PatternComponent> {
@@ -1,31 +1,52 @@
 //! You can use some function in this mod to receive message or event with a session
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use crate::Code;
+use crate::api::ApiRequest;
 use crate::session::Session;
 use crate::message::event::EventPacket;
-use crate::error::{assert, HttpResult};
+use crate::error::HttpResult;
+use crate::transport::Method;
 
 impl Session {
     async fn get_message(&self, is_fetch: bool, is_newest: bool, count: usize) -> HttpResult<Vec<EventPacket>> {
+        #[derive(Serialize)]
+        struct Request {
+            count: usize,
+            #[serde(skip)]
+            is_fetch: bool,
+            #[serde(skip)]
+            is_newest: bool,
+        }
+
         #[derive(Deserialize)]
         struct Response {
-            code: Code,
             data: Vec<EventPacket>,
         }
 
-        let url = format!("/{is_fetch}{is_newest}Message?sessionKey={sessionKey}&count={count}",
-                          is_fetch = if is_fetch { "fetch" } else { "peek" },
-                          is_newest = if is_newest { "Latest" } else { "" },
-                          sessionKey = self.key,
-                          count = count);
+        impl ApiRequest for Request {
+            type Response = Response;
+
+            fn path(&self) -> &str {
+                match (self.is_fetch, self.is_newest) {
+                    (true, true) => "/fetchLatestMessage",
+                    (true, false) => "/fetchMessage",
+                    (false, true) => "/peekLatestMessage",
+                    (false, false) => "/peekMessage",
+                }
+            }
+
+            fn action(&self) -> &str { if self.is_fetch { "Fetching" } else { "Peeking" } }
+            fn method(&self) -> Method { Method::Get }
+        }
+
+        let response = self.call(&Request { count, is_fetch, is_newest }).await?;
 
-        let response: Response = self.client().get(&self.url(&url))
-            .send().await?
-            .json().await?;
+        #[cfg(feature = "tracing")]
+        response.data.iter().for_each(crate::trace::event_received);
 
-        assert(response.code, if is_fetch { "Fetching" } else { "Peeking" })?;
+        #[cfg(feature = "metrics")]
+        response.data.iter().for_each(|packet| crate::metrics::registry().event_received(packet));
 
         Ok(response.data)
     }
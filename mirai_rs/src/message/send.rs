@@ -2,11 +2,14 @@
 
 use serde::{Serialize, Deserialize};
 
-use crate::{Target, Code};
+use crate::Target;
+use crate::api::ApiRequest;
 use crate::message::{MessageID, MessageChain, Message};
 use crate::session::Session;
 use crate::message::channel::MessageChannel;
-use crate::error::{HttpResult, assert};
+use crate::message::event::NudgeKind;
+use crate::session::CommonResponse;
+use crate::error::HttpResult;
 
 
 impl Session {
@@ -15,55 +18,82 @@ impl Session {
         #[serde(rename_all = "camelCase")]
         #[derive(Serialize)]
         struct Request<'mc> {
-            session_key: String,
             qq: Option<Target>,
             group: Option<Target>,
             quote: Option<MessageID>,
             message_chain: &'mc MessageChain,
+            #[serde(skip)]
+            path: &'static str,
         }
 
         #[serde(rename_all = "camelCase")]
         #[derive(Deserialize)]
         struct Response {
-            code: Code,
-            message_id: Option<MessageID>,
+            message_id: MessageID,
+        }
+
+        impl ApiRequest for Request<'_> {
+            type Response = Response;
+
+            fn path(&self) -> &str { self.path }
+            fn action(&self) -> &str { "Sending" }
         }
 
         let mut req = Request {
-            session_key: self.key.clone(),
             qq: None,
             group: None,
-            quote: message.quote,
+            quote: message.quoted(),
             message_chain: &message.message_chain,
+            path: "",
         };
 
-        let message_type = match channel {
+        req.path = match channel {
             MessageChannel::Group(group) => {
                 req.group = Some(group);
 
-                "Group"
+                "/sendGroupMessage"
             }
 
             MessageChannel::Friend(friend) => {
                 req.qq = Some(friend);
 
-                "Friend"
+                "/sendFriendMessage"
             }
 
             MessageChannel::Temp { qq, group } => {
                 req.qq = Some(qq);
                 req.group = Some(group);
 
-                "Temp"
+                "/sendTempMessage"
             }
         };
 
-        let resp: Response = self.client().post(&self.url(&format!("/send{}Message", message_type)))
-            .json(&req).send().await?
-            .json().await?;
+        let resp = self.call(&req).await?;
+
+        #[cfg(feature = "metrics")]
+        crate::metrics::registry().message_sent(&channel);
+
+        Ok(resp.message_id)
+    }
+
+    /// Nudge the [target] in the [subject], the [subject] is a friend or a group which is specified by [kind].
+    pub async fn send_nudge(&self, target: Target, subject: Target, kind: NudgeKind) -> HttpResult<()> {
+        #[derive(Serialize)]
+        struct Request {
+            target: Target,
+            subject: Target,
+            kind: NudgeKind,
+        }
+
+        impl ApiRequest for Request {
+            type Response = CommonResponse;
+
+            fn path(&self) -> &str { "/sendNudge" }
+            fn action(&self) -> &str { "SendNudge" }
+        }
 
-        assert(resp.code, "Sending")?;
+        self.call(&Request { target, subject, kind }).await?;
 
-        Ok(resp.message_id.unwrap())
+        Ok(())
     }
 }
\ No newline at end of file
@@ -0,0 +1,148 @@
+//! This mod collects metrics of bots, it is only available with the `metrics` feature.
+//!
+//! # Registry
+//!
+//! All the metrics are collected in a global [`Registry`], which can be rendered in the Prometheus text format:
+//!
+//! ```ignore
+//! // in the handler of `/metrics` of your service
+//! let body = mirai::metrics::registry().render();
+//! ```
+//!
+//! The following metrics are collected:
+//!
+//! * `mirai_events_received_total{event}`: received events per [`EventPacket`] variant (or type name of unknown events).
+//! * `mirai_messages_sent_total{channel}`: sent messages per [`MessageChannel`] kind, which is `friend`, `group` or `temp`.
+//! * `mirai_api_errors_total{code}`: failed API calls per status code, see [`StatusCodeError`].
+//! * `mirai_request_duration_seconds{endpoint}`: a histogram of the latency of requests per endpoint.
+//!
+//! [`StatusCodeError`]: crate::error::StatusCodeError
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+
+use crate::Code;
+use crate::message::{EventPacket, MessageChannel};
+
+/// The upper bounds (in seconds) of the buckets of latency histograms.
+const BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+static REGISTRY: Lazy<Registry> = Lazy::new(Registry::default);
+
+/// Return the global registry.
+pub fn registry() -> &'static Registry {
+    &REGISTRY
+}
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    /// The count of observations in each bucket, not cumulative.
+    buckets: [u64; BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        if let Some(index) = BUCKETS.iter().position(|bound| value <= *bound) {
+            self.buckets[index] += 1;
+        }
+
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Registry {
+    received: Mutex<BTreeMap<String, u64>>,
+    sent: Mutex<BTreeMap<&'static str, u64>>,
+    errors: Mutex<BTreeMap<Code, u64>>,
+    latency: Mutex<BTreeMap<String, Histogram>>,
+}
+
+fn channel_kind(channel: &MessageChannel) -> &'static str {
+    match channel {
+        MessageChannel::Friend(_) => "friend",
+        MessageChannel::Group(_) => "group",
+        MessageChannel::Temp { .. } => "temp",
+    }
+}
+
+/// Escape a label value as the Prometheus text format requires, that is, `\`, `"` and line feeds are escaped.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+impl Registry {
+    pub(crate) fn event_received(&self, packet: &EventPacket) {
+        *self.received.lock().unwrap().entry(packet.name().to_string()).or_default() += 1;
+    }
+
+    pub(crate) fn message_sent(&self, channel: &MessageChannel) {
+        *self.sent.lock().unwrap().entry(channel_kind(channel)).or_default() += 1;
+    }
+
+    pub(crate) fn api_error(&self, code: Code) {
+        *self.errors.lock().unwrap().entry(code).or_default() += 1;
+    }
+
+    pub(crate) fn request_finished(&self, path: &str, latency: Duration) {
+        self.latency.lock().unwrap().entry(path.to_string()).or_default().observe(latency.as_secs_f64());
+    }
+
+    /// Return the count of received events with the given variant name, see [`EventPacket::name`].
+    pub fn received(&self, event: &str) -> u64 {
+        self.received.lock().unwrap().get(event).copied().unwrap_or_default()
+    }
+
+    /// Return the count of sent messages with the given channel kind, which is `friend`, `group` or `temp`.
+    pub fn sent(&self, channel: &str) -> u64 {
+        self.sent.lock().unwrap().get(channel).copied().unwrap_or_default()
+    }
+
+    pub fn errors(&self, code: Code) -> u64 {
+        self.errors.lock().unwrap().get(&code).copied().unwrap_or_default()
+    }
+
+    /// Render all the metrics in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        out += "# TYPE mirai_events_received_total counter\n";
+        for (event, count) in self.received.lock().unwrap().iter() {
+            writeln!(out, "mirai_events_received_total{{event=\"{}\"}} {}", escape(event), count).unwrap();
+        }
+
+        out += "# TYPE mirai_messages_sent_total counter\n";
+        for (channel, count) in self.sent.lock().unwrap().iter() {
+            writeln!(out, "mirai_messages_sent_total{{channel=\"{}\"}} {}", channel, count).unwrap();
+        }
+
+        out += "# TYPE mirai_api_errors_total counter\n";
+        for (code, count) in self.errors.lock().unwrap().iter() {
+            writeln!(out, "mirai_api_errors_total{{code=\"{}\"}} {}", code, count).unwrap();
+        }
+
+        out += "# TYPE mirai_request_duration_seconds histogram\n";
+        for (endpoint, histogram) in self.latency.lock().unwrap().iter() {
+            let endpoint = escape(endpoint);
+            let mut cumulative = 0;
+
+            for (bound, count) in BUCKETS.iter().zip(histogram.buckets.iter()) {
+                cumulative += count;
+                writeln!(out, "mirai_request_duration_seconds_bucket{{endpoint=\"{}\",le=\"{}\"}} {}", endpoint, bound, cumulative).unwrap();
+            }
+
+            writeln!(out, "mirai_request_duration_seconds_bucket{{endpoint=\"{}\",le=\"+Inf\"}} {}", endpoint, histogram.count).unwrap();
+            writeln!(out, "mirai_request_duration_seconds_sum{{endpoint=\"{}\"}} {}", endpoint, histogram.sum).unwrap();
+            writeln!(out, "mirai_request_duration_seconds_count{{endpoint=\"{}\"}} {}", endpoint, histogram.count).unwrap();
+        }
+
+        out
+    }
+}
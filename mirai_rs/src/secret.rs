@@ -0,0 +1,140 @@
+//! This mod provides the type of secrets, such as auth keys and session keys.
+//!
+//! # SecretString
+//!
+//! A [`SecretString`] is never printed: its `Debug` and `Display` forms are [`REDACTED`],
+//! and its memory is zeroed when it is dropped, so a secret doesn't leak by logging a [`Session`] or an error.
+//!
+//! ```ignore
+//! let session = connection.auth("auth_key_should_be_kept_secret").await?;
+//!
+//! // prints `Session { .., key: SecretString(REDACTED), .. }`
+//! println!("{:?}", session);
+//! ```
+//!
+//! The secret can be read by [`SecretString::expose`] explicitly.
+//!
+//! mirai-api-http takes the session key of a GET request in its query string, so URLs are secrets too,
+//! the errors of the HTTP layer are displayed with the secret query parameters redacted.
+//!
+//! [`Session`]: crate::session::Session
+
+use std::fmt::{Debug, Display, Formatter};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use zeroize::Zeroize;
+
+/// The placeholder of secrets in `Debug` forms, error messages and fixtures.
+pub const REDACTED: &str = "REDACTED";
+
+/// The names of the fields and query parameters which hold secrets in requests.
+pub(crate) const SECRET_NAMES: [&str; 3] = ["sessionKey", "authKey", "verifyKey"];
+
+/// Return `true` if the field or query parameter [name] of a request holds a secret.
+pub(crate) fn is_secret(name: &str) -> bool {
+    SECRET_NAMES.contains(&name)
+}
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct SecretString(String);
+
+impl SecretString {
+    pub fn new<S: Into<String>>(secret: S) -> SecretString {
+        SecretString(secret.into())
+    }
+
+    /// Return the secret, **it should not be logged**.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    /// Return whether the [given] bytes are the secret, in a time which doesn't depend on where they differ.
+    pub fn matches(&self, given: &[u8]) -> bool {
+        let secret = self.0.as_bytes();
+
+        secret.len() == given.len() && secret.iter().zip(given).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(secret: &str) -> Self {
+        SecretString::new(secret)
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(secret: String) -> Self {
+        SecretString::new(secret)
+    }
+}
+
+impl Debug for SecretString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SecretString({})", REDACTED)
+    }
+}
+
+impl Display for SecretString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// The secret is exposed when it is serialized, because it is sent to the server.
+impl Serialize for SecretString {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where
+        S: Serializer {
+        serializer.serialize_str(self.expose())
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretString {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where
+        D: Deserializer<'de> {
+        String::deserialize(deserializer).map(SecretString)
+    }
+}
+
+/// Replace the values of the secret query parameters in the [text], such as the url in an error message.
+pub(crate) fn redact_query(text: &str) -> String {
+    let mut redacted = String::with_capacity(text.len());
+    let mut rest = text;
+
+    let params: Vec<String> = SECRET_NAMES.iter().map(|name| format!("{}=", name)).collect();
+
+    while let Some(index) = params.iter()
+        .filter_map(|param| rest.find(param.as_str()).map(|index| index + param.len()))
+        .min() {
+        redacted += &rest[..index];
+        redacted += REDACTED;
+
+        rest = &rest[index..];
+        rest = &rest[rest.find(|c: char| c == '&' || c == ')' || c.is_whitespace()).unwrap_or(rest.len())..];
+    }
+
+    redacted + rest
+}
+
+/// Replace the string fields of the [value] whose names are secrets (decided by [is_secret]) with [`REDACTED`], recursively.
+pub(crate) fn redact_json(value: Value, is_secret: fn(&str) -> bool) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(object.into_iter().map(|(key, value)| {
+            if is_secret(&key) && value.is_string() {
+                (key, Value::String(REDACTED.to_string()))
+            } else {
+                (key, redact_json(value, is_secret))
+            }
+        }).collect()),
+
+        Value::Array(array) => Value::Array(array.into_iter().map(|value| redact_json(value, is_secret)).collect()),
+
+        value => value,
+    }
+}
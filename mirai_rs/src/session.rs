@@ -8,11 +8,17 @@
 //! use mirai::session::MiraiConnection;
 //! use reqwest::Client;
 //!
-//! let connection = MiraiConnection::new("http://localhost:8080", Client::new());
+//! let connection = MiraiConnection::new("http://localhost:8080", Client::new())?;
 //! ```
 //!
+//! The base url is validated when the connection is constructed, it may have a path prefix, such as `http://example.com/mirai/`.
+//!
 //! You can use [`MiraiConnection::about`] function to get the server status.
 //!
+//! Every request is sent by a [`Transport`], which is a reqwest client by default,
+//! you can use [`MiraiConnection::with_transport`] to answer requests in another way, see [`transport`] mod.
+//! [`MiraiConnection::record`] and [`MiraiConnection::replay`] capture the traffic into fixture files and serve them back.
+//!
 //! ## Session
 //!
 //! Second, you can use [`MiraiConnection::auth`] to authorizing, the auth key can be found in mirai-console output when it starts.
@@ -38,151 +44,319 @@
 //!
 //! If not, the useless bot will continue to receive messages, this will bring **memory leak**.
 //!
+//! ## Protocol Version
+//!
+//! mirai-rs speaks both mirai-api-http 1.x and 2.x, a connection uses 1.x by default.
+//! In 2.x, authorizing is `/verify` with a verify key (which is the auth key in 1.x), and binding is `/bind`,
+//! the functions of mirai-rs are the same, they just send different requests.
+//!
+//! ```ignore
+//! let mut connection = MiraiConnection::new("http://localhost:8080", Client::new())?;
+//! connection.detect_protocol().await?;
+//! ```
+//!
 
 #![allow(dead_code)]
 
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
 use reqwest::{Client};
+use url::Url;
 use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
 
-use crate::error::{HttpResult, assert};
+use crate::api::ApiRequest;
+use crate::error::{HttpResult, UrlError, assert};
+use crate::secret::SecretString;
+use crate::transport::{self, Body, HttpTransport, Method, Request, Transport, parse_base_url};
+use crate::transport::record::{Recorder, Replayer};
 use crate::{Code, Target};
 
+/// The base url of connections which replay fixtures, it is never requested.
+const REPLAY_URL: &str = "http://replay";
+
+/// The major version of the mirai-api-http protocol which a server speaks.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ProtocolVersion {
+    V1,
+    V2,
+}
+
+impl ProtocolVersion {
+    /// Return the protocol version of a mirai-api-http version, such as `1.9.7` or `v2.0.2`.
+    /// Versions which can not be recognized are regarded as 1.x.
+    pub fn from_version(version: &str) -> ProtocolVersion {
+        let major = version.trim_start_matches('v')
+            .split('.')
+            .next()
+            .and_then(|major| major.parse::<u32>().ok());
+
+        match major {
+            Some(major) if major >= 2 => ProtocolVersion::V2,
+            _ => ProtocolVersion::V1,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct MiraiConnection {
-    pub(crate) base_url: String,
-    pub(crate) client: Client,
+    pub(crate) base_url: Url,
+    /// The client of the default HTTP transport, it is `None` if the connection uses another transport.
+    pub(crate) client: Option<Client>,
+    pub(crate) transport: Arc<dyn Transport>,
+    pub(crate) protocol: ProtocolVersion,
 }
 
 impl MiraiConnection {
     /// Constructing a connection with a server address and a mirai client instance.
-    pub fn new(base_url: &str, client: Client) -> MiraiConnection {
-        MiraiConnection {
-            base_url: base_url.to_string(),
-            client,
-        }
+    /// The [base_url] must be an `http` or `https` url without query string, it may have a path prefix.
+    pub fn new(base_url: &str, client: Client) -> Result<MiraiConnection, UrlError> {
+        Ok(MiraiConnection {
+            base_url: parse_base_url(base_url)?,
+            client: Some(client.clone()),
+            transport: Arc::new(HttpTransport::new(client)),
+            protocol: ProtocolVersion::V1,
+        })
     }
 
-    pub fn base_url(&self) -> &str {
+    /// Constructing a connection which sends every request by the given transport.
+    pub fn with_transport<T: Transport + 'static>(base_url: &str, transport: T) -> Result<MiraiConnection, UrlError> {
+        Ok(MiraiConnection {
+            base_url: parse_base_url(base_url)?,
+            client: None,
+            transport: Arc::new(transport),
+            protocol: ProtocolVersion::V1,
+        })
+    }
+
+    /// Return a connection which records all its traffic, and the recorder which the traffic is captured by.
+    /// This connection is not affected.
+    pub fn record(&self) -> (MiraiConnection, Recorder) {
+        let recorder = Recorder::wrap(self.transport.clone());
+        let connection = MiraiConnection {
+            base_url: self.base_url.clone(),
+            client: self.client.clone(),
+            transport: Arc::new(recorder.clone()),
+            protocol: self.protocol,
+        };
+
+        (connection, recorder)
+    }
+
+    /// Constructing a connection which answers requests with the fixture file which was saved by a [`Recorder`].
+    pub fn replay<P: AsRef<Path>>(fixture: P) -> std::io::Result<MiraiConnection> {
+        let connection = MiraiConnection::with_transport(REPLAY_URL, Replayer::load(fixture)?);
+
+        Ok(connection.expect("REPLAY_URL is valid"))
+    }
+
+    /// Use the given protocol version instead of detecting it.
+    pub fn with_protocol(mut self, protocol: ProtocolVersion) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    pub fn protocol(&self) -> ProtocolVersion {
+        self.protocol
+    }
+
+    /// Detect the protocol version from the version which [`MiraiConnection::about`] returns, and use it.
+    /// Sessions which were authorized before keep their protocol versions.
+    pub async fn detect_protocol(&mut self) -> HttpResult<ProtocolVersion> {
+        let about = self.about().await?;
+
+        assert(about.code, "About")?;
+
+        self.protocol = ProtocolVersion::from_version(about.data.version());
+
+        Ok(self.protocol)
+    }
+
+    pub fn base_url(&self) -> &Url {
         &self.base_url
     }
 
-    /// Connecting the base url from this connection and the given string.
-    /// Note that this function only simply connects two string, so you must ensure the given string starts with the '/' separator.
-    pub fn url(&self, path: &str) -> String {
-        self.base_url.clone() + path
+    /// Return the url of the given [path] on the server, the path is relative to the base url even if it starts with the '/' separator.
+    pub fn url(&self, path: &str) -> Url {
+        Request::new(Method::Get, path, Body::Empty).url(&self.base_url)
+    }
+
+    /// Send the [request] by the transport of this connection, and return the raw body of the response.
+    pub(crate) async fn send(&self, request: Request) -> HttpResult<bytes::Bytes> {
+        #[cfg(feature = "tracing")]
+        let method = request.method();
+
+        #[cfg(any(feature = "tracing", feature = "metrics"))]
+        let (path, start) = (request.path().to_string(), std::time::Instant::now());
+
+        let result = self.transport.send(&self.base_url, request).await;
+
+        #[cfg(feature = "tracing")]
+        crate::trace::request_finished(method, &path, start.elapsed(), &result);
+
+        #[cfg(feature = "metrics")]
+        crate::metrics::registry().request_finished(&path, start.elapsed());
+
+        result
+    }
+
+    /// Send the [request] by the transport of this connection, and deserialize the response.
+    pub(crate) async fn request<R: DeserializeOwned>(&self, request: Request) -> HttpResult<R> {
+        let body = self.send(request).await?;
+
+        Ok(serde_json::from_slice(&body)?)
     }
 
     /// send a GET request in order to get the information of the mirai server.
     pub async fn about(&self) -> HttpResult<AboutResponse> {
-        let resp: AboutResponse = self.client.get(&self.url("/about"))
-            .send().await?
-            .json().await?;
+        #[derive(Serialize)]
+        struct Request {}
+
+        impl ApiRequest for Request {
+            type Response = AboutResponse;
 
-        Ok(resp)
+            fn path(&self) -> &str { "/about" }
+            fn action(&self) -> &str { "About" }
+            fn method(&self) -> Method { Method::Get }
+        }
+
+        self.call(&Request {}).await
     }
 
-    pub async fn auth(&self, auth_key: &str) -> HttpResult<Session> {
+    /// Authorize a new session, the [auth_key] is the verify key in mirai-api-http 2.x.
+    pub async fn auth<K: Into<SecretString>>(&self, auth_key: K) -> HttpResult<Session> {
         #[derive(Serialize)]
-        struct Request {
+        enum Request {
             #[serde(rename = "authKey")]
-            auth_key: String
+            V1(SecretString),
+            #[serde(rename = "verifyKey")]
+            V2(SecretString),
         }
 
         #[derive(Deserialize)]
         struct Response {
-            code: Code,
-            session: Option<String>,
+            session: SecretString,
         }
 
-        let req = Request {
-            auth_key: auth_key.to_string()
-        };
+        impl ApiRequest for Request {
+            type Response = Response;
+
+            fn path(&self) -> &str {
+                match self {
+                    Request::V1(_) => "/auth",
+                    Request::V2(_) => "/verify",
+                }
+            }
+
+            fn action(&self) -> &str { "Auth" }
+        }
 
-        let result: Response = self.client.post(&self.url("/auth"))
-            .json(&req).send().await?
-            .json().await?;
+        let auth_key = auth_key.into();
+        let req = match self.protocol {
+            ProtocolVersion::V1 => Request::V1(auth_key),
+            ProtocolVersion::V2 => Request::V2(auth_key),
+        };
 
-        assert(result.code, "Auth")?;
+        let result = self.call(&req).await?;
 
         Ok(Session {
             connection: self.clone(),
-            key: result.session.unwrap(),
+            key: result.session,
             bound: None,
+            released: AtomicBool::new(false),
         })
     }
 
+    /// Run a command by the mirai-api-http 1.x command API, it was removed in 2.x.
     #[deprecated()]
-    pub async fn run_command(&self, auth_key: &str, command: &str, args: &[&str]) -> HttpResult<String> {
+    pub async fn run_command<K: Into<SecretString>>(&self, auth_key: K, command: &str, args: &[&str]) -> HttpResult<String> {
         #[serde(rename_all = "camelCase")]
         #[derive(Serialize)]
         struct Request<'s> {
-            auth_key: &'s str,
+            auth_key: &'s SecretString,
             name: &'s str,
             args: &'s [&'s str],
         }
 
+        let auth_key = auth_key.into();
         let req = Request {
-            auth_key,
+            auth_key: &auth_key,
             name: command,
             args,
         };
 
-        let text = self.client.post(&self.url("/command/send"))
-            .json(&req).send().await?
-            .text().await?;
+        let body = serde_json::to_value(&req)?;
+        let body = self.send(transport::Request::new(Method::Post, "/command/send", Body::Json(body))).await?;
 
-        Ok(text)
+        Ok(String::from_utf8_lossy(&body).into_owned())
     }
 }
 
 /// # Session
 ///
 /// Session contains a connection with the server which is authorized, an Auth Key which received from server, and a bound bot.
+/// The key is redacted in the `Debug` form, see [`secret`](crate::secret) mod.
 #[derive(Debug)]
 pub struct Session {
     pub(crate) connection: MiraiConnection,
-    pub(crate) key: String,
+    pub(crate) key: SecretString,
     pub(crate) bound: Option<Target>,
+    /// It is set when the session is released, so it is not released again when it is dropped.
+    pub(crate) released: AtomicBool,
 }
 
 impl Session {
     /// Return the session key of this session
     /// **WARNING: Session Key SHOULD BE Secret.**
     pub fn key(&self) -> &str {
-        &self.key
+        self.key.expose()
     }
 
-    /// Connecting the base url from this connection and the given string.
-    /// Note that this function only simply connects two string, so you must ensure the given string starts with the '/' separator.
-    pub fn url(&self, path: &str) -> String {
+    /// Return the url of the given [path] on the server, see [`MiraiConnection::url`].
+    pub fn url(&self, path: &str) -> Url {
         self.connection.url(path)
     }
 
-    /// Return the client of this session
-    pub fn client(&self) -> &Client {
-        &self.connection.client
+    /// Return the client of this session, it is the client which the default HTTP transport uses,
+    /// or `None` if the connection was constructed by [`MiraiConnection::with_transport`].
+    pub fn client(&self) -> Option<&Client> {
+        self.connection.client.as_ref()
+    }
+
+    pub fn connection(&self) -> &MiraiConnection {
+        &self.connection
     }
 
-    /// Binding the session with the given QQ ID.
+    /// Send a [request] with the session key of this session, see [`api`](crate::api) mod.
+    pub async fn call<R: ApiRequest>(&self, request: &R) -> HttpResult<R::Response> {
+        self.connection.call_with_key(Some(&self.key), request).await
+    }
+
+    /// Binding the session with the given QQ ID, it is `/bind` in mirai-api-http 2.x.
     /// Note that one session can only bind with one QQ ID.
     pub async fn verify(&mut self, qq: Target) -> HttpResult<()> {
         #[derive(Serialize)]
         struct Request {
-            #[serde(rename = "sessionKey")]
-            session_key: String,
             qq: Target,
+            #[serde(skip)]
+            path: &'static str,
         }
 
-        let req = Request {
-            session_key: self.key.clone(),
-            qq,
-        };
+        impl ApiRequest for Request {
+            type Response = CommonResponse;
 
-        let result: CommonResponse = self.client().post(&self.url("/verify"))
-            .json(&req).send().await?
-            .json().await?;
+            fn path(&self) -> &str { self.path }
+            fn action(&self) -> &str { "Verify" }
+        }
 
-        assert(result.code, "Verify")?;
+        let path = match self.connection.protocol {
+            ProtocolVersion::V1 => "/verify",
+            ProtocolVersion::V2 => "/bind",
+        };
+
+        self.call(&Request { qq, path }).await?;
 
         self.bound = Some(qq);
 
@@ -192,37 +366,58 @@ impl Session {
     /// Release a bot which current session bound before.
     pub async fn release(&self) -> HttpResult<()> {
         unsafe {
-            self.release_unchecked(self.bound.unwrap_or(0)).await
+            self.release_unchecked(self.bound.unwrap_or(0)).await?;
         }
+
+        self.released.store(true, Ordering::SeqCst);
+
+        Ok(())
     }
 
     pub async unsafe fn release_unchecked(&self, qq: Target) -> HttpResult<()> {
-        #[derive(Serialize)]
-        struct Request {
-            #[serde(rename = "sessionKey")]
-            session_key: String,
-            qq: Target,
-        }
+        self.call(&Release { qq }).await?;
 
-        let req = Request {
-            session_key: self.key.clone(),
-            qq,
-        };
+        Ok(())
+    }
+}
 
-        let resp: CommonResponse = self.client().post(&self.url("/release"))
-            .json(&req).send().await?
-            .json().await?;
+#[derive(Serialize)]
+struct Release {
+    qq: Target,
+}
 
-        assert(resp.code, "Release")
-    }
+impl ApiRequest for Release {
+    type Response = CommonResponse;
+
+    fn path(&self) -> &str { "/release" }
+    fn action(&self) -> &str { "Release" }
 }
 
-/// release without unwrap (because current session might not bind any bot before)
+/// Release the session if it was not released, without unwrap (because current session might not bind any bot before).
+///
+/// Inside a tokio runtime the release is spawned on it, a dropped session doesn't block the runtime;
+/// otherwise it blocks on a new runtime.
 impl Drop for Session {
     fn drop(&mut self) {
-        tokio::runtime::Runtime::new().unwrap().block_on(async {
-            self.release().await
-        });
+        if self.released.load(Ordering::SeqCst) {
+            return;
+        }
+
+        let connection = self.connection.clone();
+        let key = self.key.clone();
+        let request = Release { qq: self.bound.unwrap_or(0) };
+
+        let release = async move {
+            let _ = connection.call_with_key(Some(&key), &request).await;
+        };
+
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(release);
+            }
+
+            Err(_) => tokio::runtime::Runtime::new().unwrap().block_on(release),
+        }
     }
 }
 
@@ -230,7 +425,7 @@ impl Drop for Session {
 ///
 /// The most general response from the mirai server, it only contains a state code and a message string.
 #[derive(Deserialize)]
-pub(crate) struct CommonResponse {
+pub struct CommonResponse {
     pub(crate) code: Code,
     pub(crate) msg: String,
 }
@@ -0,0 +1,74 @@
+//! Tracing instrumentation of API calls and received events, it is only available with the `tracing` feature.
+//!
+//! Every API call is in a `mirai_api` span, which records the endpoint, the ids in the request (target, member, qq, group)
+//! and the status code of the response. Every request emits an event with its latency when it finishes.
+//!
+//! Only the paths of endpoints are recorded, query strings and bodies never are, so session keys and auth keys can not leak.
+//! Errors are recorded by their `Display` forms, which redact the secrets in urls.
+
+use std::time::Duration;
+
+use serde_json::Value;
+use tracing::{field, Span};
+
+use crate::Code;
+use crate::error::HttpError;
+use crate::message::EventPacket;
+use crate::transport::Method;
+
+/// The ids which are recorded in spans, and the names of their fields in requests.
+const IDS: [(&str, &str); 4] = [
+    ("target", "target"),
+    ("member_id", "memberId"),
+    ("qq", "qq"),
+    ("group", "group"),
+];
+
+/// Return the path without the query string.
+fn endpoint(path: &str) -> &str {
+    path.split('?').next().unwrap_or_default()
+}
+
+pub(crate) fn api_span(path: &str, action: &str, fields: &Value) -> Span {
+    let span = tracing::debug_span!(
+        "mirai_api",
+        endpoint = endpoint(path),
+        action,
+        target = field::Empty,
+        member_id = field::Empty,
+        qq = field::Empty,
+        group = field::Empty,
+        code = field::Empty,
+    );
+
+    for (field, name) in IDS.iter() {
+        if let Some(id) = fields.get(name).and_then(Value::as_u64) {
+            span.record(*field, id);
+        }
+    }
+
+    span
+}
+
+pub(crate) fn record_code(code: Code) {
+    Span::current().record("code", code);
+}
+
+pub(crate) fn request_finished<T>(method: Method, path: &str, latency: Duration, result: &Result<T, HttpError>) {
+    let latency_ms = latency.as_millis() as u64;
+
+    match result {
+        Ok(_) => tracing::debug!(endpoint = endpoint(path), ?method, latency_ms, "request finished"),
+        Err(error) => tracing::warn!(
+            endpoint = endpoint(path),
+            ?method,
+            latency_ms,
+            %error,
+            "request failed"
+        ),
+    }
+}
+
+pub(crate) fn event_received(packet: &EventPacket) {
+    tracing::debug!(event = packet.name(), "event received");
+}
@@ -0,0 +1,317 @@
+//! This mod provides an abstraction over the HTTP layer which is used by [`MiraiConnection`].
+//!
+//! # Transport
+//!
+//! Every request to the mirai-api-http server is described as a [`Request`], and is sent by a [`Transport`],
+//! which answers with the raw body of the response.
+//!
+//! The default transport is [`HttpTransport`], it sends requests with a reqwest [`Client`].
+//! You can implement your own [`Transport`] to answer requests directly (for example, in tests),
+//! and construct a connection with it by [`MiraiConnection::with_transport`].
+//!
+//! The traffic of a connection can be recorded to fixture files, and replayed later, see [`record`] mod.
+//!
+//! # Url
+//!
+//! The path of a [`Request`] is relative to the base url of the connection, which may have a path prefix,
+//! such as `http://example.com/mirai/` behind a reverse proxy. The query of a request is a list of parameters,
+//! they are percent-encoded when the url is built by [`Request::url`].
+//!
+//! # Session Key
+//!
+//! The session key of a [`Request`] is kept as a [`SecretString`] until the request is sent,
+//! it is inserted into the query of GET requests and the body of POST requests by the transport.
+//! The `Debug` form of a request never prints the secrets in it, see [`secret`](crate::secret) mod.
+//!
+//! [`MiraiConnection`]: crate::session::MiraiConnection
+//! [`MiraiConnection::with_transport`]: crate::session::MiraiConnection::with_transport
+//! [`SecretString`]: crate::secret::SecretString
+
+use std::fmt::{Debug, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+
+use bytes::Bytes;
+use reqwest::Client;
+use reqwest::multipart::{Form, Part};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use url::Url;
+use url::form_urlencoded;
+
+use crate::error::{HttpResult, UrlError};
+use crate::secret::{is_secret, redact_json, redact_query, SecretString, REDACTED};
+
+pub mod record;
+
+pub type TransportFuture<'a> = Pin<Box<dyn Future<Output = HttpResult<Bytes>> + Send + 'a>>;
+
+#[serde(rename_all = "UPPERCASE")]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Method {
+    Get,
+    Post,
+}
+
+#[derive(Debug, Clone)]
+pub enum Field {
+    Text(String),
+    File {
+        file_name: String,
+        bytes: Bytes,
+    },
+}
+
+/// The body of a request, the secret fields are redacted in its `Debug` form.
+#[derive(Clone)]
+pub enum Body {
+    Empty,
+    Json(Value),
+    Multipart(Vec<(String, Field)>),
+}
+
+impl Debug for Body {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Body::Empty => f.write_str("Empty"),
+            Body::Json(json) => f.debug_tuple("Json").field(&redact_json(json.clone(), is_secret)).finish(),
+            Body::Multipart(fields) => {
+                let fields: Vec<(&String, Field)> = fields.iter().map(|(name, field)| match field {
+                    Field::Text(_) if is_secret(name) => (name, Field::Text(REDACTED.to_string())),
+                    field => (name, field.clone()),
+                }).collect();
+
+                f.debug_tuple("Multipart").field(&fields).finish()
+            }
+        }
+    }
+}
+
+/// A request to the mirai-api-http server, the [`path`] starts with the '/' separator.
+#[derive(Clone)]
+pub struct Request {
+    pub(crate) method: Method,
+    pub(crate) path: String,
+    pub(crate) query: Vec<(String, String)>,
+    pub(crate) body: Body,
+    pub(crate) session_key: Option<SecretString>,
+}
+
+impl Debug for Request {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Request")
+            .field("method", &self.method)
+            .field("path", &format_args!("{}", redact_query(&self.path_and_query())))
+            .field("body", &self.body)
+            .field("session_key", &self.session_key)
+            .finish()
+    }
+}
+
+/// The JSON body of a POST request with the session key, the key is exposed only when it is serialized.
+#[derive(Serialize)]
+struct WithSessionKey<'r> {
+    #[serde(rename = "sessionKey")]
+    session_key: &'r SecretString,
+    #[serde(flatten)]
+    fields: &'r Value,
+}
+
+/// Parse the base url of a connection, only `http` and `https` urls without query are accepted.
+/// The path of the url always ends with the '/' separator, so paths of requests are appended to it.
+pub(crate) fn parse_base_url(base_url: &str) -> Result<Url, UrlError> {
+    let mut url = Url::parse(base_url)?;
+
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(UrlError::UnsupportedScheme(url.scheme().to_string()));
+    }
+
+    if url.query().is_some() || url.fragment().is_some() {
+        return Err(UrlError::UnexpectedQuery);
+    }
+
+    if !url.path().ends_with('/') {
+        let path = url.path().to_string() + "/";
+        url.set_path(&path);
+    }
+
+    Ok(url)
+}
+
+impl Request {
+    pub fn new<S: AsRef<str>>(method: Method, path: S, body: Body) -> Request {
+        Request {
+            method,
+            path: path.as_ref().to_string(),
+            query: Vec::new(),
+            body,
+            session_key: None,
+        }
+    }
+
+    /// Set the query parameters of this request, they are not encoded yet.
+    pub fn with_query(mut self, query: Vec<(String, String)>) -> Self {
+        self.query = query;
+        self
+    }
+
+    /// Set the session key of this request, it is sent as the `sessionKey` query parameter of a GET request,
+    /// or the `sessionKey` field of a POST request.
+    pub fn with_session_key(mut self, session_key: SecretString) -> Self {
+        self.session_key = Some(session_key);
+        self
+    }
+
+    pub fn method(&self) -> Method {
+        self.method
+    }
+
+    /// Return the path without the query string.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Return the query parameters, without the session key.
+    pub fn query(&self) -> &[(String, String)] {
+        &self.query
+    }
+
+    pub fn session_key(&self) -> Option<&SecretString> {
+        self.session_key.as_ref()
+    }
+
+    /// Return the query parameters which are sent, the session key of a GET request goes first.
+    fn query_pairs(&self) -> impl Iterator<Item = (&str, &str)> {
+        let session_key = match self.method {
+            Method::Get => self.session_key.as_ref().map(|key| ("sessionKey", key.expose())),
+            Method::Post => None,
+        };
+
+        session_key.into_iter().chain(self.query.iter().map(|(name, value)| (name.as_str(), value.as_str())))
+    }
+
+    /// Return the path with the encoded query string, such as `/friendList?sessionKey=KEY`,
+    /// it contains the session key of a GET request.
+    pub fn path_and_query(&self) -> String {
+        let query = form_urlencoded::Serializer::new(String::new()).extend_pairs(self.query_pairs()).finish();
+
+        if query.is_empty() {
+            self.path.clone()
+        } else {
+            format!("{}?{}", self.path, query)
+        }
+    }
+
+    /// Return the url of this request on the server at [base_url], which is parsed by [`MiraiConnection::new`].
+    ///
+    /// [`MiraiConnection::new`]: crate::session::MiraiConnection::new
+    pub fn url(&self, base_url: &Url) -> Url {
+        let mut url = base_url.clone();
+        let path = url.path().to_string() + self.path.trim_start_matches('/');
+
+        url.set_path(&path);
+
+        if self.query_pairs().next().is_some() {
+            url.query_pairs_mut().extend_pairs(self.query_pairs());
+        }
+
+        url
+    }
+
+    /// Return the body without the session key.
+    pub fn body(&self) -> &Body {
+        &self.body
+    }
+
+    /// Return the body which is recorded for this request, the session key of a POST request is inserted as [`REDACTED`].
+    ///
+    /// The body is `null` if it is empty, and the text fields of a multipart form are an object
+    /// (files are described with their names and sizes only).
+    pub(crate) fn redacted_body(&self) -> Value {
+        let mut body = match &self.body {
+            Body::Empty => Value::Null,
+            Body::Json(json) => redact_json(json.clone(), is_secret),
+            Body::Multipart(fields) => Value::Object(fields.iter().map(|(name, field)| {
+                let value = match field {
+                    Field::Text(_) if is_secret(name) => Value::String(REDACTED.to_string()),
+                    Field::Text(text) => Value::String(text.clone()),
+                    Field::File { file_name, bytes } => {
+                        let mut file = Map::new();
+                        file.insert("fileName".to_string(), Value::String(file_name.clone()));
+                        file.insert("size".to_string(), Value::from(bytes.len()));
+
+                        Value::Object(file)
+                    }
+                };
+
+                (name.clone(), value)
+            }).collect()),
+        };
+
+        if let (Method::Post, Some(_), Value::Object(body)) = (self.method, &self.session_key, &mut body) {
+            body.insert("sessionKey".to_string(), Value::String(REDACTED.to_string()));
+        }
+
+        body
+    }
+}
+
+pub trait Transport: Debug + Send + Sync {
+    /// Send the [request] to the server at [base_url], and return the body of the response.
+    fn send<'a>(&'a self, base_url: &'a Url, request: Request) -> TransportFuture<'a>;
+}
+
+/// A [`Transport`] which sends requests with a reqwest [`Client`].
+#[derive(Debug, Clone)]
+pub struct HttpTransport {
+    client: Client,
+}
+
+impl HttpTransport {
+    pub fn new(client: Client) -> HttpTransport {
+        HttpTransport { client }
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+}
+
+impl Transport for HttpTransport {
+    fn send<'a>(&'a self, base_url: &'a Url, request: Request) -> TransportFuture<'a> {
+        Box::pin(async move {
+            // the url contains the session key of GET requests, it must not be logged, see `secret` mod
+            let url = request.url(base_url);
+
+            let builder = match request.method {
+                Method::Get => self.client.get(url),
+                Method::Post => self.client.post(url),
+            };
+
+            let builder = match (request.body, &request.session_key) {
+                (Body::Empty, _) => builder,
+                (Body::Json(json), None) => builder.json(&json),
+                (Body::Json(json), Some(session_key)) => builder.json(&WithSessionKey { session_key, fields: &json }),
+                (Body::Multipart(fields), session_key) => {
+                    let form = match session_key {
+                        Some(session_key) => Form::new().text("sessionKey", session_key.expose().to_string()),
+                        None => Form::new(),
+                    };
+
+                    let form = fields.into_iter().fold(form, |form, (name, field)| {
+                        match field {
+                            Field::Text(text) => form.text(name, text),
+                            Field::File { file_name, bytes } => {
+                                form.part(name, Part::stream(reqwest::Body::from(bytes)).file_name(file_name))
+                            }
+                        }
+                    });
+
+                    builder.multipart(form)
+                }
+            };
+
+            Ok(builder.send().await?.bytes().await?)
+        })
+    }
+}
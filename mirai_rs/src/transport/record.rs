@@ -0,0 +1,234 @@
+//! Recording and replaying the traffic between a client and a mirai-api-http server.
+//!
+//! # Recorder
+//!
+//! A [`Recorder`] wraps another transport, every request and its response are captured as an [`Exchange`].
+//! The captured exchanges can be saved to a JSON fixture file by [`Recorder::save`].
+//!
+//! ```ignore
+//! let (connection, recorder) = MiraiConnection::new("http://localhost:8080", Client::new())?.record();
+//!
+//! let session = connection.auth("auth_key").await?;
+//! // ...
+//!
+//! recorder.save("fixtures/mirai-api-http-1.9.json")?;
+//! ```
+//!
+//! Secrets are never written to fixtures, session keys, auth keys and verify keys are replaced with [`REDACTED`].
+//!
+//! # Replayer
+//!
+//! A [`Replayer`] answers requests with the responses in a [`Fixture`], no server is needed.
+//!
+//! ```ignore
+//! let connection = MiraiConnection::replay("fixtures/mirai-api-http-1.9.json")?;
+//! let session = connection.auth("any auth key").await?;
+//! ```
+//!
+//! A request is answered by the first exchange which has not been replayed and has the same method, path and body
+//! (after redacting), so a fixture can be replayed only once.
+
+use std::fmt::{Debug, Formatter};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use url::Url;
+
+use crate::error::HttpError;
+use crate::secret::{redact_json, REDACTED};
+use crate::transport::{Method, Request, Transport, TransportFuture};
+
+/// Return `true` if the field or query parameter [name] holds a secret,
+/// `session` is the session key in the response of authorizing.
+fn is_secret(name: &str) -> bool {
+    crate::secret::is_secret(name) || name == "session"
+}
+
+/// A request and its response.
+///
+/// The [`request`] is the JSON body of the request, it is `null` for requests without a body,
+/// and the text fields of a multipart form are recorded as an object (files are recorded with their names and sizes only).
+///
+/// The [`response`] is the JSON body of the response, or a string if the body is not JSON.
+#[serde(rename_all = "camelCase")]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Exchange {
+    pub method: Method,
+    pub path: String,
+    pub request: Value,
+    pub response: Value,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Fixture {
+    pub(crate) exchanges: Vec<Exchange>,
+}
+
+impl Fixture {
+    pub fn new(exchanges: Vec<Exchange>) -> Fixture {
+        Fixture { exchanges }
+    }
+
+    pub fn exchanges(&self) -> &[Exchange] {
+        &self.exchanges
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> std::io::Result<Fixture> {
+        let file = File::open(path)?;
+
+        Ok(serde_json::from_reader(BufReader::new(file))?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        let file = File::create(path)?;
+
+        Ok(serde_json::to_writer_pretty(BufWriter::new(file), self)?)
+    }
+}
+
+/// Replace the secret query parameters of the [path] with [`REDACTED`].
+fn redact_path(path: &str) -> String {
+    match path.find('?') {
+        None => path.to_string(),
+        Some(index) => {
+            let (path, query) = path.split_at(index);
+            let query: Vec<String> = query[1..].split('&').map(|pair| {
+                match pair.find('=') {
+                    Some(eq) if is_secret(&pair[..eq]) => format!("{}={}", &pair[..eq], REDACTED),
+                    _ => pair.to_string(),
+                }
+            }).collect();
+
+            format!("{}?{}", path, query.join("&"))
+        }
+    }
+}
+
+/// Return the method, path and body of the [request], with secrets redacted.
+fn redact_request(request: &Request) -> (Method, String, Value) {
+    (request.method, redact_path(&request.path_and_query()), request.redacted_body())
+}
+
+/// A [`Transport`] which captures every exchange that it sends by another transport.
+///
+/// It is cheap to clone, all the clones share the same captured exchanges.
+/// Requests which failed (no response was received) are not captured.
+#[derive(Clone)]
+pub struct Recorder {
+    inner: Arc<dyn Transport>,
+    exchanges: Arc<Mutex<Vec<Exchange>>>,
+}
+
+impl Debug for Recorder {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Recorder")
+            .field("inner", &self.inner)
+            .field("exchanges", &self.exchanges.lock().unwrap().len())
+            .finish()
+    }
+}
+
+impl Recorder {
+    pub fn new<T: Transport + 'static>(inner: T) -> Recorder {
+        Recorder::wrap(Arc::new(inner))
+    }
+
+    pub(crate) fn wrap(inner: Arc<dyn Transport>) -> Recorder {
+        Recorder {
+            inner,
+            exchanges: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Return a fixture which contains all the exchanges captured until now.
+    pub fn fixture(&self) -> Fixture {
+        Fixture::new(self.exchanges.lock().unwrap().clone())
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        self.fixture().save(path)
+    }
+}
+
+impl Transport for Recorder {
+    fn send<'a>(&'a self, base_url: &'a Url, request: Request) -> TransportFuture<'a> {
+        Box::pin(async move {
+            let (method, path, body) = redact_request(&request);
+            let response = self.inner.send(base_url, request).await?;
+
+            let json = match serde_json::from_slice(&response) {
+                Ok(json) => redact_json(json, is_secret),
+                Err(_) => Value::String(String::from_utf8_lossy(&response).into_owned()),
+            };
+
+            self.exchanges.lock().unwrap().push(Exchange {
+                method,
+                path,
+                request: body,
+                response: json,
+            });
+
+            Ok(response)
+        })
+    }
+}
+
+/// A [`Transport`] which answers requests with the exchanges in a [`Fixture`].
+///
+/// Sending a request which is not in the fixture fails with [`HttpError::Replay`].
+#[derive(Debug)]
+pub struct Replayer {
+    /// The exchanges and whether they were replayed.
+    exchanges: Mutex<Vec<(Exchange, bool)>>,
+}
+
+impl Replayer {
+    pub fn new(fixture: Fixture) -> Replayer {
+        Replayer {
+            exchanges: Mutex::new(fixture.exchanges.into_iter().map(|exchange| (exchange, false)).collect()),
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> std::io::Result<Replayer> {
+        Ok(Replayer::new(Fixture::load(path)?))
+    }
+
+    /// Return the exchanges which have not been replayed.
+    pub fn remaining(&self) -> Vec<Exchange> {
+        self.exchanges.lock().unwrap().iter()
+            .filter(|(_, replayed)| !replayed)
+            .map(|(exchange, _)| exchange.clone())
+            .collect()
+    }
+}
+
+impl Transport for Replayer {
+    fn send<'a>(&'a self, _: &'a Url, request: Request) -> TransportFuture<'a> {
+        Box::pin(async move {
+            let (method, path, body) = redact_request(&request);
+            let mut exchanges = self.exchanges.lock().unwrap();
+
+            let found = exchanges.iter_mut().find(|(exchange, replayed)| {
+                !*replayed && exchange.method == method && exchange.path == path && exchange.request == body
+            });
+
+            match found {
+                Some((exchange, replayed)) => {
+                    *replayed = true;
+
+                    Ok(match &exchange.response {
+                        Value::String(text) => Bytes::from(text.clone()),
+                        json => Bytes::from(serde_json::to_vec(json)?),
+                    })
+                }
+
+                None => Err(HttpError::Replay(format!("{:?} {}", method, path))),
+            }
+        })
+    }
+}
@@ -0,0 +1,275 @@
+//! This mod provides a server which receives the events pushed by the webhook adapter of mirai-api-http 2.x,
+//! it is only available with the `webhook` feature.
+//!
+//! # Webhook
+//!
+//! With the webhook adapter, mirai-api-http posts every event to the configured destinations,
+//! so the API port doesn't need to be exposed. A [`Webhook`] listens on a local address and receives these events.
+//!
+//! ```ignore
+//! use mirai::webhook::Webhook;
+//!
+//! let webhook = Webhook::new(([127, 0, 0, 1], 8081).into())
+//!     .authorization("Authorization", "a secret which is configured in extraHeaders");
+//!
+//! let (address, mut events) = webhook.listen()?;
+//!
+//! while let Some(event) = events.recv().await {
+//!     // ...
+//! }
+//! ```
+//!
+//! [`Webhook::listen`] returns the same [`BotEvents`] as [`BotManager::listen`], so a bot can switch between polling and webhook easily.
+//! The server stops when the [`BotEvents`] is dropped.
+//!
+//! The bot can also reply inline: the reply which [`Webhook::serve`]'s handler returns is sent as the body of the webhook response,
+//! it is a command which mirai-api-http executes with the bot which received the event.
+//!
+//! The bot of an event is read from the `qq` header of the push, pushes without it are rejected.
+//! Reverse WebSocket is not supported yet.
+//!
+//! [`BotManager::listen`]: crate::manager::BotManager::listen
+//! [`BotEvents`]: crate::manager::BotEvents
+
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use hyper::service::{make_service_fn, service_fn};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::Target;
+use crate::error::HttpError;
+use crate::manager::{BotError, BotEvent, BotEvents};
+use crate::secret::SecretString;
+use crate::message::{EventPacket, Message, MessageChain, MessageChannel, MessageID};
+
+/// The header which carries the bot of a push.
+const BOT_HEADER: &str = "qq";
+
+type ReplyFuture = Pin<Box<dyn Future<Output = Option<WebhookReply>> + Send>>;
+type Handler = Arc<dyn Fn(Result<BotEvent, BotError>) -> ReplyFuture + Send + Sync>;
+
+/// A command which is sent back to mirai-api-http as the body of a webhook response.
+#[derive(Debug, Clone)]
+pub enum WebhookReply {
+    /// Send the [message] to the [channel] by the bot which received the event.
+    Message {
+        channel: MessageChannel,
+        message: Message,
+    },
+}
+
+impl WebhookReply {
+    pub fn message(channel: MessageChannel, message: Message) -> WebhookReply {
+        WebhookReply::Message { channel, message }
+    }
+
+    fn to_json(&self) -> Value {
+        #[serde(rename_all = "camelCase")]
+        #[derive(Serialize)]
+        struct Content<'mc> {
+            #[serde(skip_serializing_if = "Option::is_none")]
+            target: Option<Target>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            qq: Option<Target>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            group: Option<Target>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            quote: Option<MessageID>,
+            message_chain: &'mc MessageChain,
+        }
+
+        match self {
+            WebhookReply::Message { channel, message } => {
+                let mut content = Content {
+                    target: None,
+                    qq: None,
+                    group: None,
+                    quote: message.quoted(),
+                    message_chain: message.message_chain(),
+                };
+
+                let command = match *channel {
+                    MessageChannel::Friend(friend) => {
+                        content.target = Some(friend);
+
+                        "sendFriendMessage"
+                    }
+
+                    MessageChannel::Group(group) => {
+                        content.target = Some(group);
+
+                        "sendGroupMessage"
+                    }
+
+                    MessageChannel::Temp { qq, group } => {
+                        content.qq = Some(qq);
+                        content.group = Some(group);
+
+                        "sendTempMessage"
+                    }
+                };
+
+                json! {{
+                    "command": command,
+                    "content": content,
+                }}
+            }
+        }
+    }
+}
+
+/// A server which receives webhook pushes, the value of the authorization header is redacted in the `Debug` form.
+#[derive(Debug, Clone)]
+pub struct Webhook {
+    address: SocketAddr,
+    authorization: Option<(String, SecretString)>,
+}
+
+impl Webhook {
+    /// Construct a webhook server which listens on the given [address], use port 0 to let the system choose one.
+    pub fn new(address: SocketAddr) -> Webhook {
+        Webhook {
+            address,
+            authorization: None,
+        }
+    }
+
+    /// Reject the pushes whose [header] is not [value].
+    /// The header should be configured in the `extraHeaders` of the webhook adapter.
+    pub fn authorization<H: AsRef<str>, V: Into<SecretString>>(mut self, header: H, value: V) -> Self {
+        self.authorization = Some((header.as_ref().to_string(), value.into()));
+        self
+    }
+
+    /// Start the server in a new task, and return the address which it listens on and the received events.
+    /// Pushes which can not be deserialized are received as errors, every push is answered without a reply.
+    /// The server stops when the events are dropped.
+    pub fn listen(self) -> hyper::Result<(SocketAddr, BotEvents)> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let (stop, stopped) = oneshot::channel::<()>();
+
+        let address = self.spawn(Arc::new(move |event| {
+            let _ = tx.send(event);
+
+            Box::pin(async { None })
+        }), async {
+            let _ = stopped.await;
+        })?;
+
+        Ok((address, BotEvents::webhook(rx, stop)))
+    }
+
+    /// Start the server in a new task, and return the address which it listens on.
+    /// Every event is handled by the [handler], the reply which it returns is sent as the body of the webhook response.
+    pub fn serve<F, Fut>(self, handler: F) -> hyper::Result<SocketAddr> where
+        F: Fn(BotEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Option<WebhookReply>> + Send + 'static {
+        self.spawn(Arc::new(move |event| {
+            match event {
+                Ok(event) => Box::pin(handler(event)),
+                Err(_) => Box::pin(async { None }),
+            }
+        }), futures::future::pending())
+    }
+
+    /// Start the server in a new task, it stops when [shutdown] completes.
+    fn spawn<S: Future<Output = ()> + Send + 'static>(self, handler: Handler, shutdown: S) -> hyper::Result<SocketAddr> {
+        let authorization = Arc::new(self.authorization);
+
+        let make_service = make_service_fn(move |_| {
+            let handler = handler.clone();
+            let authorization = authorization.clone();
+
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    handle(handler.clone(), authorization.clone(), request)
+                }))
+            }
+        });
+
+        let server = Server::try_bind(&self.address)?.serve(make_service);
+        let address = server.local_addr();
+
+        tokio::spawn(server.with_graceful_shutdown(shutdown));
+
+        Ok(address)
+    }
+}
+
+fn status(code: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = code;
+
+    response
+}
+
+async fn handle(handler: Handler, authorization: Arc<Option<(String, SecretString)>>, request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    if request.method() != Method::POST {
+        return Ok(status(StatusCode::METHOD_NOT_ALLOWED));
+    }
+
+    if let Some((header, value)) = authorization.as_ref() {
+        let authorized = request.headers().get(header.as_str())
+            .map(|given| value.matches(given.as_bytes()))
+            .unwrap_or(false);
+
+        if !authorized {
+            return Ok(status(StatusCode::UNAUTHORIZED));
+        }
+    }
+
+    let bot: Option<Target> = request.headers().get(BOT_HEADER)
+        .and_then(|bot| bot.to_str().ok())
+        .and_then(|bot| bot.parse().ok());
+
+    let bot = match bot {
+        Some(bot) => bot,
+        None => return Ok(status(StatusCode::BAD_REQUEST)),
+    };
+
+    let body = match hyper::body::to_bytes(request.into_body()).await {
+        Ok(body) => body,
+        Err(_) => return Ok(status(StatusCode::BAD_REQUEST)),
+    };
+
+    let event = match serde_json::from_slice::<EventPacket>(&body) {
+        Ok(packet) => {
+            #[cfg(feature = "tracing")]
+            crate::trace::event_received(&packet);
+
+            #[cfg(feature = "metrics")]
+            crate::metrics::registry().event_received(&packet);
+
+            Ok(BotEvent { bot, packet })
+        }
+
+        Err(error) => Err(BotError { bot, error: HttpError::from(error) }),
+    };
+
+    let rejected = event.is_err();
+    let reply = handler(event).await;
+
+    if rejected {
+        return Ok(status(StatusCode::BAD_REQUEST));
+    }
+
+    Ok(match reply {
+        Some(reply) => {
+            #[cfg(feature = "metrics")]
+            match &reply {
+                WebhookReply::Message { channel, .. } => crate::metrics::registry().message_sent(channel),
+            }
+
+            Response::new(Body::from(reply.to_json().to_string()))
+        }
+
+        None => status(StatusCode::OK),
+    })
+}
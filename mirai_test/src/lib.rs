@@ -1,5 +1,8 @@
 pub mod data;
+pub mod scenario;
+pub mod subscriber;
 pub mod tests;
+pub mod transport;
 
 pub const HOST: &'static str = "localhost";
 pub const PORT: u16 = 8080;
\ No newline at end of file
@@ -0,0 +1,268 @@
+//! A harness to test bots like chat transcripts, built on the mock server.
+//!
+//! # Scenario
+//!
+//! A [`Scenario`] spawns a [`MockServer`] on a free port, the bot under test connects to it by [`Scenario::connection`].
+//! Then you can let users say something, and expect the bot to reply:
+//!
+//! ```ignore
+//! let mut scenario = Scenario::new()?;
+//! tokio::spawn(run_my_bot(scenario.session().await?));
+//!
+//! scenario.user(11).in_group(100).says("/roll")?;
+//! scenario.expect_reply(MessageChannel::Group(100))
+//!     .matching(|text| text.starts_with("You rolled"))
+//!     .within(Duration::from_secs(1)).await?;
+//! ```
+//!
+//! Each expectation consumes the first matching message which was sent after the message consumed by the last expectation,
+//! messages which are skipped can not be expected anymore.
+//!
+//! [`MockServer`]: mirai_rs_mock::mock::MockServer
+
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, Instant};
+
+use reqwest::Client;
+
+use mirai::{Code, Target};
+use mirai::error::HttpResult;
+use mirai::message::{MessageChain, MessageChannel, MessageContent, MessageID};
+use mirai::session::{MiraiConnection, Session};
+use mirai_rs_mock::handle::MockHandle;
+use mirai_rs_mock::mock::{MockServer, SpawnedServer};
+use mirai_rs_mock::world::{SentMessage, World};
+
+/// How often an expectation checks the sent messages.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+pub struct Scenario {
+    handle: MockHandle,
+    /// The mock server is stopped when the scenario is dropped.
+    server: SpawnedServer,
+    bot: Target,
+    /// The count of sent messages which can not be expected anymore.
+    cursor: usize,
+}
+
+/// An expectation which was not satisfied in time.
+#[derive(Debug)]
+pub struct ScenarioError {
+    expected: String,
+    /// The messages which were checked, but did not match.
+    checked: Vec<SentMessage>,
+}
+
+pub struct User<'s> {
+    scenario: &'s Scenario,
+    qq: Target,
+    group: Option<Target>,
+}
+
+type Matcher = Box<dyn Fn(&SentMessage) -> bool>;
+
+pub struct Expectation<'s> {
+    scenario: &'s mut Scenario,
+    channel: MessageChannel,
+    matchers: Vec<(String, Matcher)>,
+}
+
+/// Concatenate the elements of the [message_chain] as text, non-plain elements are written in their display forms.
+/// Unknown elements are skipped, such as the `Source` of a received message.
+pub fn text_of(message_chain: &MessageChain) -> String {
+    message_chain.iter()
+        .filter(|content| !matches!(content, MessageContent::Unknown { .. }))
+        .map(ToString::to_string)
+        .collect()
+}
+
+impl Scenario {
+    /// Spawn a mock server with [`World::default`], the bot under test is `1`.
+    pub fn new() -> std::io::Result<Scenario> {
+        Scenario::with_world(World::default(), 1)
+    }
+
+    /// Spawn a mock server with the given [world], the [bot] is the bot under test.
+    pub fn with_world(world: World, bot: Target) -> std::io::Result<Scenario> {
+        let server = MockServer::new("127.0.0.1", 0).world(world);
+        let handle = server.handle();
+        let server = server.spawn()?;
+
+        Ok(Scenario {
+            handle,
+            server,
+            bot,
+            cursor: 0,
+        })
+    }
+
+    pub fn handle(&self) -> &MockHandle {
+        &self.handle
+    }
+
+    pub fn bot(&self) -> Target {
+        self.bot
+    }
+
+    pub fn connection(&self) -> MiraiConnection {
+        MiraiConnection::new(&format!("http://{}", self.server.address()), Client::new()).expect("the address of the mock server is a valid url")
+    }
+
+    /// Authorize a session and bind it with the bot under test.
+    pub async fn session(&self) -> HttpResult<Session> {
+        let auth_key = self.handle.world().auth_key.clone();
+        let mut session = self.connection().auth(auth_key).await?;
+        session.verify(self.bot).await?;
+
+        Ok(session)
+    }
+
+    /// Return a user who can talk to the bot, the user talks as a friend unless [`User::in_group`] is called.
+    pub fn user(&self, qq: Target) -> User<'_> {
+        User {
+            scenario: self,
+            qq,
+            group: None,
+        }
+    }
+
+    /// Expect the bot to send a message to the given [channel].
+    pub fn expect_reply(&mut self, channel: MessageChannel) -> Expectation<'_> {
+        Expectation {
+            scenario: self,
+            channel,
+            matchers: Vec::new(),
+        }
+    }
+
+    /// Expect the bot to send nothing for [duration].
+    pub async fn expect_silence(&mut self, duration: Duration) -> Result<(), ScenarioError> {
+        tokio::time::delay_for(duration).await;
+
+        let sent = self.handle.sent_messages();
+
+        if sent.len() > self.cursor {
+            Err(ScenarioError {
+                expected: format!("no message in {:?}", duration),
+                checked: sent[self.cursor..].to_vec(),
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl User<'_> {
+    pub fn in_group(mut self, group: Target) -> Self {
+        self.group = Some(group);
+        self
+    }
+
+    /// Send the [message_chain] to the bot, and return the id of the message.
+    pub fn sends(&self, message_chain: MessageChain) -> Result<MessageID, Code> {
+        let handle = &self.scenario.handle;
+        let bot = self.scenario.bot;
+
+        match self.group {
+            Some(group) => handle.group_message(bot, group, self.qq, message_chain),
+            None => handle.friend_message(bot, self.qq, message_chain),
+        }
+    }
+
+    /// Send a plain text message to the bot, and return the id of the message.
+    pub fn says<S: AsRef<str>>(&self, text: S) -> Result<MessageID, Code> {
+        self.sends(vec![text.as_ref().into()])
+    }
+}
+
+impl<'s> Expectation<'s> {
+    fn matcher<S: AsRef<str>, F: Fn(&SentMessage) -> bool + 'static>(mut self, description: S, f: F) -> Self {
+        self.matchers.push((description.as_ref().to_string(), Box::new(f)));
+        self
+    }
+
+    /// The text of the reply (see [`text_of`]) satisfies the [predicate].
+    pub fn matching<F: Fn(&str) -> bool + 'static>(self, predicate: F) -> Self {
+        self.matcher("matching the predicate", move |message| predicate(&text_of(&message.message_chain)))
+    }
+
+    pub fn containing<S: AsRef<str>>(self, text: S) -> Self {
+        let text = text.as_ref().to_string();
+
+        self.matcher(format!("containing {:?}", text), move |message| text_of(&message.message_chain).contains(&text))
+    }
+
+    pub fn equal_to<S: AsRef<str>>(self, text: S) -> Self {
+        let text = text.as_ref().to_string();
+
+        self.matcher(format!("equal to {:?}", text), move |message| text_of(&message.message_chain) == text)
+    }
+
+    /// The reply quotes the message [id].
+    pub fn quoting(self, id: MessageID) -> Self {
+        self.matcher(format!("quoting {}", id), move |message| message.quote == Some(id))
+    }
+
+    fn describe(&self) -> String {
+        let mut description = format!("a reply to {:?}", self.channel);
+
+        for (matcher, _) in &self.matchers {
+            description += " ";
+            description += matcher;
+        }
+
+        description
+    }
+
+    fn matches(&self, message: &SentMessage) -> bool {
+        message.bot == self.scenario.bot
+            && message.channel == self.channel
+            && self.matchers.iter().all(|(_, matcher)| matcher(message))
+    }
+
+    /// Wait until the bot sends a matching message, and return the message.
+    pub async fn within(self, timeout: Duration) -> Result<SentMessage, ScenarioError> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let sent = self.scenario.handle.sent_messages();
+            let found = sent.iter().enumerate()
+                .skip(self.scenario.cursor)
+                .find(|(_, message)| self.matches(message));
+
+            if let Some((index, message)) = found {
+                let message = message.clone();
+                self.scenario.cursor = index + 1;
+
+                return Ok(message);
+            }
+
+            if Instant::now() >= deadline {
+                return Err(ScenarioError {
+                    expected: format!("{} within {:?}", self.describe(), timeout),
+                    checked: sent[self.scenario.cursor.min(sent.len())..].to_vec(),
+                });
+            }
+
+            tokio::time::delay_for(POLL_INTERVAL).await;
+        }
+    }
+}
+
+impl Display for ScenarioError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Expected {}, but the bot sent:", self.expected)?;
+
+        if self.checked.is_empty() {
+            writeln!(f, "  (nothing)")?;
+        }
+
+        for message in &self.checked {
+            writeln!(f, "  {:?}: {}", message.channel, text_of(&message.message_chain))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for ScenarioError {}
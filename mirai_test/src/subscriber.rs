@@ -0,0 +1,117 @@
+//! A tracing [`Subscriber`] which captures the fields of all the spans and events as text, to check what is traced.
+//!
+//! ```ignore
+//! let subscriber = CapturingSubscriber::new();
+//! let _guard = tracing::subscriber::set_default(subscriber.clone());
+//!
+//! session.send_message(MessageChannel::Group(100), &message).await?;
+//!
+//! assert!(subscriber.captured().contains("endpoint=/sendGroupMessage"));
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use tracing::{Event, Metadata, Subscriber};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing_core::span::Current;
+
+/// A [`Subscriber`] which enables everything, and writes each span, record and event as a line of `name=value` pairs.
+///
+/// It tracks the entered spans, so that fields can be recorded to [`Span::current`].
+/// It is cheap to clone, all the clones share the same captured lines.
+///
+/// [`Span::current`]: tracing::Span::current
+#[derive(Clone, Default)]
+pub struct CapturingSubscriber {
+    lines: Arc<Mutex<Vec<String>>>,
+    next_id: Arc<AtomicU64>,
+    spans: Arc<Mutex<HashMap<u64, &'static Metadata<'static>>>>,
+    entered: Arc<Mutex<Vec<Id>>>,
+}
+
+/// Writes the visited fields to a line, strings are written without quotes.
+struct Line(String);
+
+impl Visit for Line {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.push_str(&format!(" {}={}", field.name(), value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0.push_str(&format!(" {}={:?}", field.name(), value));
+    }
+}
+
+impl CapturingSubscriber {
+    pub fn new() -> CapturingSubscriber {
+        CapturingSubscriber::default()
+    }
+
+    /// Return all the captured lines, joined by newlines.
+    pub fn captured(&self) -> String {
+        self.lines.lock().unwrap().join("\n")
+    }
+
+    fn capture(&self, line: Line) {
+        self.lines.lock().unwrap().push(line.0);
+    }
+}
+
+impl Subscriber for CapturingSubscriber {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        let mut line = Line(format!("span {} {}", id, span.metadata().name()));
+        span.record(&mut line);
+        self.capture(line);
+        self.spans.lock().unwrap().insert(id, span.metadata());
+
+        Id::from_u64(id)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        let mut line = Line(format!("record {}", span.into_u64()));
+        values.record(&mut line);
+        self.capture(line);
+    }
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut line = Line(format!("event {}", event.metadata().level()));
+        event.record(&mut line);
+        self.capture(line);
+    }
+
+    fn enter(&self, span: &Id) {
+        self.entered.lock().unwrap().push(span.clone());
+    }
+
+    fn exit(&self, span: &Id) {
+        let mut entered = self.entered.lock().unwrap();
+
+        if let Some(index) = entered.iter().rposition(|id| id == span) {
+            entered.remove(index);
+        }
+    }
+
+    fn current_span(&self) -> Current {
+        let current = self.entered.lock().unwrap().last().cloned();
+
+        match current {
+            Some(id) => {
+                let metadata = self.spans.lock().unwrap()[&id.into_u64()];
+                Current::new(id, metadata)
+            }
+
+            None => Current::none(),
+        }
+    }
+}
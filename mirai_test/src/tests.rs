@@ -1,17 +1,1020 @@
 #![cfg(test)]
 #![allow(dead_code)]
 
-use mirai::session::MiraiConnection;
+use std::sync::Arc;
+use std::time::Duration;
+
+use mirai::Target;
+use mirai::message::{EventPacket, Message, MessageChannel};
+use mirai::message::element::Permission;
+use mirai::message::event::{ChangeType, GroupChangeEvent, MessageEvent, NudgeKind, RecallEvent};
+use mirai::api::ApiRequest;
+use mirai::batch::BatchOptions;
+use mirai::cache::ContactCache;
+use mirai::manager::{BotEvents, BotManager};
+use mirai::image::ImageType;
+use mirai::group::{self, MemberInfo, GroupConfigChange, GroupConfigField, GroupConfigPatch, MemberInfoChange, NewAnnouncement, MAX_MUTE};
+use mirai::secret::{SecretString, REDACTED};
+use mirai::session::{MiraiConnection, ProtocolVersion, Session};
+use mirai::error::{HttpError, UrlError, MESSAGE_TOO_LONG, NO_SUCH_BOT, NO_SUCH_TARGET, PERMISSION_DENIED, WRONG_AUTH_KEY, WRONG_SESSION};
+use mirai::transport::{Body, Method, Request};
+use mirai::transport::record::Replayer;
+use mirai::webhook::{Webhook, WebhookReply};
+use mirai_rs_mock::world::{Bot as MockBot, Group as MockGroup, Member as MockMember, Permission as MockPermission, World};
+use bytes::Bytes;
 use reqwest::Client;
+use serde_json::json;
 
 use crate::{PORT, HOST};
 use crate::data::session::About;
+use crate::scenario::{text_of, Scenario};
+use crate::subscriber::CapturingSubscriber;
+use crate::transport::FakeTransport;
 
 fn connection() -> MiraiConnection {
-    MiraiConnection::new(&format!("http://{}:{}", HOST, PORT), Client::new())
+    MiraiConnection::new(&format!("http://{}:{}", HOST, PORT), Client::new()).unwrap()
 }
 
-// #[tokio::test]
+#[tokio::test]
 async fn about() {
-    assert_eq!(About::response(), connection().about().await.unwrap());
-}
\ No newline at end of file
+    let transport = FakeTransport::new();
+    transport.respond("/about", About::response());
+
+    assert_eq!(About::response(), transport.connection().about().await.unwrap());
+
+    let requests = transport.requests();
+    assert_eq!(1, requests.len());
+    assert_eq!("/about", requests[0].path());
+}
+
+#[tokio::test]
+async fn fake_transport() {
+    let transport = FakeTransport::new();
+    let connection = transport.connection();
+
+    // a request without a handler panics, but doesn't poison the transport
+    let unhandled = connection.clone();
+    assert!(tokio::spawn(async move { unhandled.about().await }).await.unwrap_err().is_panic());
+
+    transport.respond("/about", About::response());
+    transport.respond("/auth", json! {{ "code": 0, "session": "SESSION" }});
+    assert_eq!(About::response(), connection.about().await.unwrap());
+
+    // the session doesn't have a client which bypasses the transport
+    let session = connection.auth("AUTH_KEY").await.unwrap();
+    assert!(session.client().is_none());
+    assert_eq!(2, transport.requests().len());
+}
+
+#[tokio::test]
+async fn record_and_replay() {
+    let transport = FakeTransport::new();
+    transport.respond("/auth", json! {{ "code": 0, "session": "SECRET_SESSION" }});
+    transport.respond("/verify", json! {{ "code": 0, "msg": "success" }});
+    transport.respond("/about", About::response());
+
+    let (connection, recorder) = transport.connection().record();
+
+    let mut session = connection.auth("SECRET_AUTH_KEY").await.unwrap();
+    session.verify(1).await.unwrap();
+    connection.about().await.unwrap();
+
+    let fixture = recorder.fixture();
+    let recorded = serde_json::to_string(&fixture).unwrap();
+    assert_eq!(3, fixture.exchanges().len());
+    assert!(!recorded.contains("SECRET"));
+
+    let replayer = Replayer::new(fixture);
+    let connection = MiraiConnection::with_transport("http://replay", replayer).unwrap();
+
+    let mut session = connection.auth("another auth key").await.unwrap();
+    session.verify(1).await.unwrap();
+    assert_eq!(About::response(), connection.about().await.unwrap());
+    assert!(connection.about().await.is_err());
+
+    // mirai-api-http 2.x authorizes with a verify key
+    let transport = FakeTransport::new();
+    transport.respond("/verify", json! {{ "code": 0, "session": "SECRET_SESSION" }});
+    transport.respond("/bind", json! {{ "code": 0, "msg": "success" }});
+    transport.respond("/friendList", json! {{ "code": 0, "msg": "", "data": [] }});
+
+    let (connection, recorder) = transport.connection().with_protocol(ProtocolVersion::V2).record();
+
+    let mut session = connection.auth("SECRET_VERIFY_KEY").await.unwrap();
+    session.verify(1).await.unwrap();
+    session.friend_list().await.unwrap();
+
+    let fixture = recorder.fixture();
+    let recorded = serde_json::to_string(&fixture).unwrap();
+    assert_eq!(json!({ "verifyKey": REDACTED }), fixture.exchanges()[0].request);
+    assert!(!recorded.contains("SECRET"));
+
+    let connection = MiraiConnection::with_transport("http://replay", Replayer::new(fixture)).unwrap().with_protocol(ProtocolVersion::V2);
+
+    let mut session = connection.auth("another verify key").await.unwrap();
+    session.verify(1).await.unwrap();
+    assert!(session.friend_list().await.unwrap().is_empty());
+}
+
+/// A bot which replies `pong` to `/ping`, in groups only.
+async fn ping_bot(session: Session) {
+    loop {
+        for packet in session.fetch_message(10).await.unwrap() {
+            if let EventPacket::MessageEvent(MessageEvent::GroupMessage { message_chain, sender }) = packet {
+                if text_of(&message_chain) == "/ping" {
+                    let channel = MessageChannel::Group(sender.group().id());
+                    session.send_message(channel, &Message::new(vec!["pong".into()])).await.unwrap();
+                }
+            }
+        }
+
+        tokio::time::delay_for(Duration::from_millis(10)).await;
+    }
+}
+
+#[tokio::test]
+async fn scenario() {
+    let mut scenario = Scenario::new().unwrap();
+    let session = scenario.session().await.unwrap();
+    tokio::spawn(ping_bot(session));
+
+    scenario.user(11).in_group(100).says("/ping").unwrap();
+    scenario.expect_reply(MessageChannel::Group(100))
+        .equal_to("pong")
+        .within(Duration::from_secs(1)).await.unwrap();
+
+    scenario.user(10).says("/ping").unwrap();
+    scenario.expect_silence(Duration::from_millis(100)).await.unwrap();
+
+    let error = scenario.expect_reply(MessageChannel::Group(100))
+        .within(Duration::from_millis(50)).await.unwrap_err();
+    assert!(error.to_string().contains("nothing"));
+}
+
+#[tokio::test]
+async fn spawned_server() {
+    let scenario = Scenario::new().unwrap();
+    let connection = scenario.connection();
+    connection.about().await.unwrap();
+
+    drop(scenario);
+    assert!(connection.about().await.is_err());
+}
+
+#[tokio::test]
+async fn protocol_v2() {
+    let transport = FakeTransport::new();
+    transport.respond("/about", json! {{ "code": 0, "data": { "version": "2.0.2" } }});
+    transport.respond("/verify", json! {{ "code": 0, "session": "SESSION" }});
+    transport.respond("/bind", json! {{ "code": 0, "msg": "success" }});
+    transport.respond("/friendList", json! {{
+        "code": 0,
+        "msg": "",
+        "data": [{ "id": 10, "nickname": "Friend", "remark": "Friend" }]
+    }});
+
+    let mut connection = transport.connection();
+    assert_eq!(ProtocolVersion::V2, connection.detect_protocol().await.unwrap());
+
+    let mut session = connection.auth("VERIFY_KEY").await.unwrap();
+    session.verify(1).await.unwrap();
+
+    let friends = session.friend_list().await.unwrap();
+    assert_eq!(10, friends[0].id());
+
+    let requests = transport.requests();
+    let paths: Vec<String> = requests.iter().map(Request::path_and_query).collect();
+    assert_eq!(vec!["/about", "/verify", "/bind", "/friendList?sessionKey=SESSION"], paths);
+}
+
+#[tokio::test]
+async fn upload_image() {
+    let transport = FakeTransport::new();
+    transport.respond("/auth", json! {{ "code": 0, "session": "SESSION" }});
+
+    // mirai-api-http 2.x doesn't return the path of the image
+    transport.respond("/uploadImage", json! {{ "imageId": "{IMAGE-ID}.png", "url": "http://image" }});
+
+    let session = transport.connection().auth("AUTH_KEY").await.unwrap();
+    let image = session.upload_image(ImageType::Group, Bytes::from_static(b"PNG"), "image.png".to_string()).await.unwrap();
+    assert_eq!("{IMAGE-ID}.png", image.image_id);
+    assert_eq!(None, image.path);
+
+    // the fields are sent as text parts of the form, as mirai-api-http requires, the session key is added when it is sent
+    let request = transport.requests().pop().unwrap();
+    assert_eq!(Some("SESSION"), request.session_key().map(SecretString::expose));
+
+    match request.body() {
+        Body::Multipart(form) => {
+            let names: Vec<&str> = form.iter().map(|(name, _)| name.as_str()).collect();
+            assert_eq!(vec!["type", "img"], names);
+        }
+
+        body => panic!("unexpected body: {:?}", body),
+    }
+
+    // an upload goes through the same status code check and metrics as other endpoints
+    let errors = mirai::metrics::registry().errors(WRONG_SESSION);
+    transport.respond("/uploadImage", json! {{ "code": WRONG_SESSION, "msg": "" }});
+
+    match session.upload_image(ImageType::Group, Bytes::from_static(b"PNG"), "image.png".to_string()).await {
+        Err(HttpError::StatusCode(error)) => {
+            assert_eq!(WRONG_SESSION, error.code());
+            assert_eq!("UploadImage", error.action());
+        }
+
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    assert!(mirai::metrics::registry().errors(WRONG_SESSION) > errors);
+}
+
+#[tokio::test]
+async fn webhook() {
+    let webhook = Webhook::new(([127, 0, 0, 1], 0).into()).authorization("Authorization", String::from("SECRET"));
+    assert!(!format!("{:?}", webhook).contains("SECRET"));
+
+    let address = webhook
+        .serve(|event| async move {
+            match event.packet {
+                EventPacket::MessageEvent(MessageEvent::GroupMessage { sender, .. }) => {
+                    let channel = MessageChannel::Group(sender.group().id());
+                    Some(WebhookReply::message(channel, Message::new(vec!["pong".into()])))
+                }
+
+                _ => None,
+            }
+        })
+        .unwrap();
+
+    let push = json! {{
+        "type": "GroupMessage",
+        "messageChain": [{ "type": "Plain", "text": "/ping" }],
+        "sender": {
+            "id": 11,
+            "memberName": "Member",
+            "permission": "MEMBER",
+            "group": { "id": 100, "name": "Group", "permission": "OWNER" }
+        }
+    }};
+
+    let client = Client::new();
+    let url = format!("http://{}", address);
+
+    let rejected = client.post(&url).header("qq", "1").json(&push).send().await.unwrap();
+    assert_eq!(401, rejected.status().as_u16());
+
+    let reply: serde_json::Value = client.post(&url)
+        .header("qq", "1")
+        .header("Authorization", "SECRET")
+        .json(&push)
+        .send().await.unwrap()
+        .json().await.unwrap();
+
+    assert_eq!(reply, json! {{
+        "command": "sendGroupMessage",
+        "content": {
+            "target": 100,
+            "messageChain": [{ "type": "Plain", "text": "pong" }]
+        }
+    }});
+}
+
+#[tokio::test]
+async fn webhook_listen() {
+    // the same consumer works with both polling and webhook
+    async fn next_bot(events: &mut BotEvents) -> Target {
+        events.recv().await.unwrap().unwrap().bot
+    }
+
+    let (address, mut events) = Webhook::new(([127, 0, 0, 1], 0).into()).listen().unwrap();
+    let url = format!("http://{}", address);
+    let push = json! {{ "type": "BotOnlineEvent", "qq": 1 }};
+
+    let response = Client::new().post(&url).header("qq", "1").json(&push).send().await.unwrap();
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(1, next_bot(&mut events).await);
+
+    // the server stops when the events are dropped
+    drop(events);
+    tokio::time::delay_for(Duration::from_millis(50)).await;
+    assert!(Client::new().post(&url).header("qq", "1").json(&push).send().await.is_err());
+}
+
+#[tokio::test]
+async fn api_request() {
+    let transport = FakeTransport::new();
+    transport.respond("/auth", json! {{ "code": 0, "session": "SESSION" }});
+    transport.respond("/mute", json! {{ "code": PERMISSION_DENIED, "msg": "没有权限" }});
+
+    let session = transport.connection().auth("AUTH_KEY").await.unwrap();
+
+    match session.mute(100, 11, Duration::from_secs(60)).await {
+        Err(HttpError::StatusCode(error)) => {
+            assert_eq!(PERMISSION_DENIED, error.code());
+            assert_eq!("Mute", error.action());
+        }
+
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    assert_eq!(Some("SESSION"), transport.requests()[1].session_key().map(SecretString::expose));
+
+    match transport.requests()[1].body() {
+        Body::Json(body) => assert_eq!(body, &json! {{
+            "target": 100,
+            "memberId": 11,
+            "time": 60
+        }}),
+
+        body => panic!("unexpected body: {:?}", body),
+    }
+}
+
+#[tokio::test]
+async fn metrics() {
+    let registry = mirai::metrics::registry();
+    let (sent, errors) = (registry.sent("temp"), registry.errors(PERMISSION_DENIED));
+
+    let transport = FakeTransport::new();
+    transport.respond("/auth", json! {{ "code": 0, "session": "SESSION" }});
+    transport.respond("/sendTempMessage", json! {{ "code": 0, "msg": "success", "messageId": 1 }});
+    transport.respond("/muteAll", json! {{ "code": PERMISSION_DENIED, "msg": "没有权限" }});
+    transport.respond("/fetchMessage", json! {{ "code": 0, "data": [{ "type": "Quoted\"Back\\slash\nEvent" }] }});
+
+    let session = transport.connection().auth("AUTH_KEY").await.unwrap();
+    session.send_message(MessageChannel::Temp { qq: 11, group: 100 }, &Message::new(vec!["Hello".into()])).await.unwrap();
+    assert!(session.mute_all(100).await.is_err());
+    session.fetch_message(10).await.unwrap();
+
+    assert!(registry.sent("temp") > sent);
+    assert!(registry.errors(PERMISSION_DENIED) > errors);
+
+    let rendered = registry.render();
+    assert!(rendered.contains("mirai_messages_sent_total{channel=\"temp\"}"));
+    assert!(rendered.contains("mirai_api_errors_total{code=\"10\"}"));
+    assert!(rendered.contains("mirai_request_duration_seconds_count{endpoint=\"/sendTempMessage\"} "));
+    assert!(rendered.contains(r#"mirai_events_received_total{event="Quoted\"Back\\slash\nEvent"} 1"#));
+}
+
+#[tokio::test]
+async fn tracing() {
+    let subscriber = CapturingSubscriber::new();
+    let _guard = tracing::subscriber::set_default(subscriber.clone());
+
+    let transport = FakeTransport::new();
+    transport.respond("/auth", json! {{ "code": 0, "session": "SECRET_SESSION" }});
+    transport.respond("/verify", json! {{ "code": 0, "msg": "success" }});
+    transport.respond("/sendGroupMessage", json! {{ "code": 0, "msg": "success", "messageId": 1 }});
+    transport.respond("/mute", json! {{ "code": PERMISSION_DENIED, "msg": "没有权限" }});
+    transport.respond("/fetchMessage", json! {{ "code": 0, "data": [{ "type": "BotOnlineEvent", "qq": 1 }] }});
+
+    let mut session = transport.connection().auth("SECRET_AUTH_KEY").await.unwrap();
+    session.verify(1).await.unwrap();
+    session.send_message(MessageChannel::Group(100), &Message::new(vec!["Hello".into()])).await.unwrap();
+    assert!(session.mute(100, 11, Duration::from_secs(60)).await.is_err());
+    assert_eq!(1, session.fetch_message(10).await.unwrap().len());
+
+    // mirai-api-http 2.x authorizes with a verify key
+    transport.respond("/verify", json! {{ "code": 0, "session": "SECRET_SESSION" }});
+    transport.respond("/bind", json! {{ "code": 0, "msg": "success" }});
+
+    let mut session = transport.connection().with_protocol(ProtocolVersion::V2).auth("SECRET_VERIFY_KEY").await.unwrap();
+    session.verify(1).await.unwrap();
+
+    let captured = subscriber.captured();
+    let expected = [
+        "span 1 mirai_api endpoint=/auth action=Auth",
+        "record 1 code=0",
+        "span 2 mirai_api endpoint=/verify action=Verify",
+        "record 2 qq=1",
+        "span 3 mirai_api endpoint=/sendGroupMessage action=Sending",
+        "record 3 group=100",
+        "span 4 mirai_api endpoint=/mute action=Mute",
+        "record 4 target=100",
+        "record 4 member_id=11",
+        &format!("record 4 code={}", PERMISSION_DENIED),
+        "event DEBUG message=request finished endpoint=/fetchMessage method=Get latency_ms=",
+        "event DEBUG message=event received event=BotLoginEvent",
+        "span 6 mirai_api endpoint=/verify action=Auth",
+    ];
+
+    for line in expected.iter() {
+        assert!(captured.contains(line), "{} is not captured:\n{}", line, captured);
+    }
+
+    assert!(!captured.contains("SECRET"), "{}", captured);
+}
+
+#[tokio::test]
+async fn secret_keys() {
+    #[serde(rename_all = "camelCase")]
+    #[derive(serde::Serialize)]
+    struct Request {
+        session_key: &'static str,
+    }
+
+    impl ApiRequest for Request {
+        type Response = serde_json::Value;
+
+        fn path(&self) -> &str { "/friendList" }
+        fn action(&self) -> &str { "FriendList" }
+        fn method(&self) -> Method { Method::Get }
+    }
+
+    let transport = FakeTransport::new();
+    transport.respond("/auth", json! {{ "code": 0, "session": "SECRET_SESSION" }});
+
+    let session = transport.connection().auth("SECRET_AUTH_KEY").await.unwrap();
+    assert_eq!("SECRET_SESSION", session.key());
+    assert!(!format!("{:?}", session).contains("SECRET_SESSION"));
+    assert_eq!("REDACTED", SecretString::from("SECRET_AUTH_KEY").to_string());
+    assert!(SecretString::from("SECRET_AUTH_KEY").matches(b"SECRET_AUTH_KEY"));
+    assert!(!SecretString::from("SECRET_AUTH_KEY").matches(b"SECRET_AUTH_KEZ"));
+    assert!(!SecretString::from("SECRET_AUTH_KEY").matches(b"SECRET"));
+
+    // nothing listens on port 1, the error contains the url
+    let connection = MiraiConnection::new("http://127.0.0.1:1", Client::new()).unwrap();
+    let error = connection.call(&Request { session_key: "SECRET_SESSION" }).await.unwrap_err();
+
+    assert!(matches!(error, HttpError::Reqwest(_)));
+    assert!(error.to_string().contains("sessionKey=REDACTED"));
+    assert!(!format!("{:?}", error).contains("SECRET_SESSION"));
+
+    // the session key is kept as a secret until the request is sent, and requests never print secrets
+    let request = mirai::transport::Request::new(Method::Get, "/friendList", Body::Empty)
+        .with_session_key(SecretString::from("SECRET_SESSION"));
+    assert_eq!("/friendList?sessionKey=SECRET_SESSION", request.path_and_query());
+    assert!(!format!("{:?}", request).contains("SECRET_SESSION"));
+
+    let request = mirai::transport::Request::new(Method::Post, "/command/send", Body::Json(json! {{ "authKey": "SECRET_AUTH_KEY", "name": "stop" }}))
+        .with_session_key(SecretString::from("SECRET_SESSION"));
+    let debug = format!("{:?}", request);
+    assert_eq!("/command/send", request.path_and_query());
+    assert!(!debug.contains("SECRET_SESSION") && !debug.contains("SECRET_AUTH_KEY") && debug.contains("stop"));
+}
+
+#[test]
+fn base_url() {
+    for base_url in &["http://example.com/mirai", "http://example.com/mirai/"] {
+        let connection = MiraiConnection::new(base_url, Client::new()).unwrap();
+        assert_eq!("http://example.com/mirai/about", connection.url("/about").as_str());
+    }
+
+    let connection = MiraiConnection::new("http://example.com/mirai/", Client::new()).unwrap();
+    let request = Request::new(Method::Get, "/groupConfig", Body::Empty)
+        .with_query(vec![("sessionKey".to_string(), "a&b c".to_string()), ("target".to_string(), "100".to_string())]);
+
+    assert_eq!("http://example.com/mirai/groupConfig?sessionKey=a%26b+c&target=100", request.url(connection.base_url()).as_str());
+    assert_eq!("/groupConfig?sessionKey=a%26b+c&target=100", request.path_and_query());
+
+    assert!(matches!(MiraiConnection::new("not a url", Client::new()), Err(UrlError::Parse(_))));
+    assert!(matches!(MiraiConnection::new("localhost:8080", Client::new()), Err(UrlError::UnsupportedScheme(_))));
+    assert!(matches!(MiraiConnection::new("http://localhost:8080/?a=b", Client::new()), Err(UrlError::UnexpectedQuery)));
+}
+
+#[tokio::test]
+async fn member_info() {
+    let scenario = Scenario::new().unwrap();
+    let session = scenario.session().await.unwrap();
+
+    session.modify_member_info(100, 11, &MemberInfoChange::new().name("Renamed")).await.unwrap();
+    session.modify_member_info(100, 11, &MemberInfoChange::new().special_title("Title")).await.unwrap();
+
+    let info = session.get_member_info(100, 11).await.unwrap();
+    assert_eq!("Renamed", info.name());
+    assert_eq!("Title", info.special_title());
+
+    let requests = scenario.handle().requests();
+    let request = requests.iter().find(|request| request.path == "/memberInfo").unwrap();
+    assert_eq!(json! {{ "name": "Renamed" }}, request.body["info"]);
+
+    scenario.handle().world().bots.get_mut(&1).unwrap().group_mut(100).unwrap().permission = MockPermission::Administrator;
+    scenario.handle().clear_records();
+
+    match session.modify_member_info(100, 11, &MemberInfoChange::new().special_title("Denied")).await {
+        Err(HttpError::StatusCode(error)) => assert_eq!(PERMISSION_DENIED, error.code()),
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    assert!(scenario.handle().requests().iter().all(|request| request.path != "/memberInfo"));
+    session.modify_member_info(100, 11, &MemberInfoChange::new().name("Admin Renamed")).await.unwrap();
+
+    let change = MemberInfoChange::from(MemberInfo::new("Name", String::from("Title")));
+    assert_eq!(json! {{ "name": "Name", "specialTitle": "Title" }}, serde_json::to_value(&change).unwrap());
+}
+
+#[tokio::test]
+async fn group_admin() {
+    let scenario = Scenario::new().unwrap();
+    let session = scenario.session().await.unwrap();
+
+    session.set_admin(100, 11, true).await.unwrap();
+    assert_eq!(MockPermission::Administrator, scenario.handle().world().bots[&1].groups[&100].members[&11].permission);
+
+    let id = session.send_message(MessageChannel::Group(100), &Message::new(vec!["Essence".into()])).await.unwrap();
+    session.set_essence(id).await.unwrap();
+    assert!(scenario.handle().world().sent[&id].essence);
+
+    scenario.handle().world().bots.get_mut(&1).unwrap().group_mut(100).unwrap().permission = MockPermission::Administrator;
+    scenario.handle().clear_records();
+
+    match session.set_admin(100, 11, false).await {
+        Err(HttpError::StatusCode(error)) => assert_eq!(PERMISSION_DENIED, error.code()),
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    assert!(scenario.handle().requests().iter().all(|request| request.path != "/memberAdmin"));
+
+    // an administrator can set essence messages too
+    let id = session.send_message(MessageChannel::Group(100), &Message::new(vec!["Essence".into()])).await.unwrap();
+    session.set_essence(id).await.unwrap();
+
+    // the server checks the permission in the group of the message
+    scenario.handle().world().bots.get_mut(&1).unwrap().group_mut(100).unwrap().permission = MockPermission::Member;
+
+    match session.set_essence(id).await {
+        Err(HttpError::StatusCode(error)) => assert_eq!(PERMISSION_DENIED, error.code()),
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    match session.set_essence(id + 100).await {
+        Err(HttpError::StatusCode(error)) => assert_eq!(NO_SUCH_TARGET, error.code()),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[tokio::test]
+async fn announcements() {
+    let scenario = Scenario::new().unwrap();
+    let session = scenario.session().await.unwrap();
+
+    let rules = NewAnnouncement::new("Weekly rules")
+        .pinned(true)
+        .send_to_new_member(true)
+        .show_popup(true)
+        .image_url("http://example.com/rules.png");
+
+    let published = session.publish_announcement(100, &rules).await.unwrap();
+    assert_eq!("Weekly rules", published.content());
+    assert_eq!(100, published.group().id());
+    assert_eq!(1, published.sender_id());
+
+    let requests = scenario.handle().requests();
+    let request = requests.iter().find(|request| request.path == "/anno/publish").unwrap();
+    assert_eq!(json!(true), request.body["pinned"]);
+    assert_eq!(json!("http://example.com/rules.png"), request.body["imageUrl"]);
+    assert!(request.body.get("imagePath").is_none());
+
+    let announcements = session.list_announcements(100, 0, 10).await.unwrap();
+    assert_eq!(1, announcements.len());
+    assert_eq!(published.fid(), announcements[0].fid());
+
+    session.delete_announcement(100, published.fid()).await.unwrap();
+    assert!(session.list_announcements(100, 0, 10).await.unwrap().is_empty());
+
+    match session.delete_announcement(100, published.fid()).await {
+        Err(HttpError::StatusCode(error)) => assert_eq!(NO_SUCH_TARGET, error.code()),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[tokio::test]
+async fn batch() {
+    let scenario = Scenario::new().unwrap();
+    let session = scenario.session().await.unwrap();
+    let options = BatchOptions::new().concurrency(2).interval(Duration::from_millis(50));
+
+    let start = std::time::Instant::now();
+    let report = session.mute_many(100, &[11, 999, 10], Duration::from_secs(60), &options).await;
+
+    assert!(start.elapsed() >= Duration::from_millis(100));
+    assert_eq!(vec![11, 999, 10], report.results().iter().map(|(member, _)| *member).collect::<Vec<_>>());
+    assert_eq!(vec![11, 10], report.succeeded());
+    assert!(!report.is_success());
+
+    match report.failed().as_slice() {
+        [(999, HttpError::StatusCode(error))] => assert_eq!(NO_SUCH_TARGET, error.code()),
+        failed => panic!("unexpected failures: {:?}", failed),
+    }
+
+    scenario.handle().world().bots.get_mut(&1).unwrap().group_mut(100).unwrap().permission = MockPermission::Administrator;
+
+    let report = session.kick_many(100, &[10, 11], "spam", &options).await;
+    assert_eq!(vec![11], report.succeeded());
+
+    match report.failed().as_slice() {
+        [(10, HttpError::StatusCode(error))] => assert_eq!(PERMISSION_DENIED, error.code()),
+        failed => panic!("unexpected failures: {:?}", failed),
+    }
+}
+
+#[tokio::test]
+async fn mute_state() {
+    let scenario = Scenario::new().unwrap();
+    let session = scenario.session().await.unwrap();
+
+    let remaining = |scenario: &Scenario| scenario.handle().world().bots[&1].groups[&100].members[&11].mute_time_remaining;
+
+    session.mute(100, 11, Duration::from_secs(90)).await.unwrap();
+    assert_eq!(90, remaining(&scenario));
+
+    for duration in &[Duration::from_millis(500), MAX_MUTE + Duration::from_secs(1)] {
+        match session.mute(100, 11, *duration).await {
+            Err(HttpError::InvalidArgument(_)) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    session.mute(100, 11, MAX_MUTE).await.unwrap();
+    assert_eq!(MAX_MUTE.as_secs(), u64::from(remaining(&scenario)));
+
+    // the mock server speaks mirai-api-http 1.x, which doesn't report the mute state
+    scenario.handle().clear_records();
+    assert!(matches!(session.mute_time_remaining(100, 11).await, Err(HttpError::Unsupported(_))));
+    assert!(matches!(session.is_mute_all(100).await, Err(HttpError::Unsupported(_))));
+    assert!(scenario.handle().requests().is_empty());
+
+    let transport = FakeTransport::new();
+    transport.respond("/verify", json! {{ "code": 0, "session": "SESSION" }});
+    transport.respond("/bind", json! {{ "code": 0, "msg": "success" }});
+    transport.respond("/memberInfo", json! {{ "memberName": "Member", "specialTitle": "", "muteTimeRemaining": 90 }});
+    transport.respond("/groupConfig", json! {{
+        "name": "Group",
+        "confessTalk": false,
+        "allowMemberInvite": false,
+        "autoApprove": false,
+        "anonymousChat": false,
+        "muteAll": true
+    }});
+
+    let mut session = transport.connection().with_protocol(ProtocolVersion::V2).auth("VERIFY_KEY").await.unwrap();
+    session.verify(1).await.unwrap();
+    assert_eq!(Duration::from_secs(90), session.mute_time_remaining(100, 11).await.unwrap());
+    assert!(session.is_mute_all(100).await.unwrap());
+
+    // a server which doesn't report the mute state is not regarded as unmuted
+    transport.respond("/memberInfo", json! {{ "memberName": "Member", "specialTitle": "" }});
+    assert!(matches!(session.mute_time_remaining(100, 11).await, Err(HttpError::Unsupported(_))));
+}
+
+#[tokio::test]
+async fn group_config_patch() {
+    let scenario = Scenario::new().unwrap();
+    let session = scenario.session().await.unwrap();
+
+    let old = session.get_group_config(100).await.unwrap();
+
+    assert!(GroupConfigPatch::new().is_empty());
+    scenario.handle().clear_records();
+    session.patch_group_config(100, &GroupConfigPatch::new().name("Renamed").allow_member_invite(!old.allow_member_invite)).await.unwrap();
+
+    let requests = scenario.handle().requests();
+    let request = requests.iter().find(|request| request.path == "/groupConfig").unwrap();
+    assert_eq!(json!({ "name": "Renamed", "allowMemberInvite": !old.allow_member_invite }), request.body["config"]);
+
+    let new = session.get_group_config(100).await.unwrap();
+    let changes = group::diff(&old, &new);
+    assert_eq!(vec![
+        GroupConfigChange::new(GroupConfigField::Name, ChangeType::String { origin: old.name.clone(), current: "Renamed".to_string() }),
+        GroupConfigChange::new(GroupConfigField::AllowMemberInvite, ChangeType::Bool { origin: old.allow_member_invite, current: !old.allow_member_invite }),
+    ], changes);
+    assert_eq!(GroupConfigPatch::new().name("Renamed").allow_member_invite(!old.allow_member_invite), GroupConfigPatch::from_diff(&old, &new));
+
+    let mut applied = old.clone();
+    changes.iter().for_each(|change| change.apply(&mut applied));
+    assert!(group::diff(&applied, &new).is_empty());
+
+    let event: GroupChangeEvent = serde_json::from_value(json!({
+        "type": "GroupNameChangeEvent",
+        "origin": old.name,
+        "current": "Renamed",
+        "group": { "id": 100, "name": "Renamed", "permission": "OWNER" },
+        "operator": null
+    })).unwrap();
+
+    assert!(event.operator().is_none());
+    assert!(changes[0].matches(&event));
+    assert!(!changes[1].matches(&event));
+
+    let event: GroupChangeEvent = serde_json::from_value(json!({
+        "type": "GroupAllowConfessTalkEvent",
+        "origin": old.confess_talk,
+        "current": !old.confess_talk,
+        "group": { "id": 100, "name": "Renamed", "permission": "OWNER" },
+        "isByBot": false
+    })).unwrap();
+
+    let change = GroupConfigChange::new(GroupConfigField::ConfessTalk, ChangeType::Bool { origin: old.confess_talk, current: !old.confess_talk });
+    assert_eq!(GroupConfigField::ConfessTalk, GroupConfigField::of_event(event.kind()));
+    assert!(change.matches(&event));
+}
+
+#[tokio::test]
+async fn contact_cache() {
+    let scenario = Scenario::new().unwrap();
+    let session = scenario.session().await.unwrap();
+    let cache = ContactCache::new();
+
+    assert_eq!(100, cache.group_by_name(&session, "Mock Group").await.unwrap().unwrap().id());
+    assert_eq!(11, cache.member_by_name(&session, 100, "Mock Member").await.unwrap().unwrap().id());
+    assert_eq!(10, cache.friend_by_name(&session, "Mock Friend").await.unwrap().unwrap().id());
+
+    scenario.handle().clear_records();
+
+    let handle = scenario.handle();
+    handle.member_join(1, 100, MockMember::new(12, "Newcomer", MockPermission::Member)).unwrap();
+    handle.member_card_change(1, 100, 11, "Renamed").unwrap();
+    handle.member_permission_change(1, 100, 11, MockPermission::Administrator).unwrap();
+    handle.member_leave(1, 100, 10, None).unwrap();
+    handle.bot_join_group(1, MockGroup::new(200, "Second Group", MockPermission::Member)).unwrap();
+
+    let update = || async {
+        for packet in session.fetch_message(10).await.unwrap() {
+            assert!(!matches!(packet, EventPacket::Unknown { .. }), "unexpected event: {:?}", packet);
+            cache.update(&packet);
+        }
+    };
+
+    update().await;
+
+    assert!(cache.member(&session, 100, 12).await.unwrap().is_some());
+    assert!(cache.member(&session, 100, 10).await.unwrap().is_none());
+    assert!(cache.member_by_name(&session, 100, "Mock Member").await.unwrap().is_none());
+
+    let renamed = cache.member_by_name(&session, 100, "Renamed").await.unwrap().unwrap();
+    assert_eq!(11, renamed.id());
+    assert_eq!(Permission::Administrator, renamed.permission());
+    assert_eq!(Some(200), cache.group_by_name(&session, "Second Group").await.unwrap().map(|group| group.id()));
+
+    handle.bot_permission_change(1, 100, MockPermission::Administrator).unwrap();
+    update().await;
+
+    assert_eq!(Permission::Administrator, cache.group(&session, 100).await.unwrap().unwrap().permission());
+    assert_eq!(Permission::Administrator, cache.member(&session, 100, 11).await.unwrap().unwrap().group().permission());
+
+    handle.bot_leave_group(1, 100, true).unwrap();
+    handle.bot_join_group(1, MockGroup::new(300, "Disbanded Group", MockPermission::Member)).unwrap();
+    update().await;
+    handle.bot_group_disband(1, 300).unwrap();
+    update().await;
+
+    assert!(cache.group(&session, 100).await.unwrap().is_none());
+    assert_eq!(vec![200], cache.groups(&session).await.unwrap().iter().map(|group| group.id()).collect::<Vec<_>>());
+    assert!(handle.requests().iter().all(|request| request.path.ends_with("fetchMessage")));
+
+    // the bot left the group, so the member list can not be requested again
+    cache.invalidate();
+    match cache.members(&session, 100).await {
+        Err(HttpError::StatusCode(error)) => assert_eq!(NO_SUCH_TARGET, error.code()),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[tokio::test]
+async fn contact_cache_loading() {
+    let cache = Arc::new(ContactCache::new());
+    let transport = FakeTransport::new();
+    transport.respond("/auth", json! {{ "code": 0, "session": "SESSION" }});
+
+    let group = |id| json! {{ "id": id, "name": "Group", "permission": "MEMBER" }};
+    let member = move |id, group_id| json! {{ "id": id, "memberName": "Member", "permission": "MEMBER", "group": group(group_id) }};
+    let event = |event| serde_json::from_value::<EventPacket>(event).unwrap();
+
+    // the updates arrive after the server answered, but before the lists are received
+    let updating = cache.clone();
+    transport.respond_with("/groupList", move |_| {
+        updating.update(&event(json! {{ "type": "BotGroupPermissionChangeEvent", "origin": "MEMBER", "current": "OWNER", "group": group(100) }}));
+        json!([group(100), group(200)])
+    });
+
+    let updating = cache.clone();
+    transport.respond_with("/memberList", move |request| {
+        if request.path_and_query().contains("target=200") {
+            updating.update(&event(json! {{ "type": "BotLeaveEventDisband", "group": group(200), "operator": null }}));
+            json!([member(20, 200)])
+        } else {
+            updating.update(&event(json! {{ "type": "MemberLeaveEventQuit", "member": member(11, 100) }}));
+            json!([member(10, 100), member(11, 100)])
+        }
+    });
+
+    let session = transport.connection().auth("AUTH_KEY").await.unwrap();
+    assert_eq!(Permission::Owner, cache.group(&session, 100).await.unwrap().unwrap().permission());
+    assert!(cache.member(&session, 100, 11).await.unwrap().is_none());
+    assert!(cache.member(&session, 100, 10).await.unwrap().is_some());
+
+    // the member list of a group which the bot left is not cached
+    cache.members(&session, 200).await.unwrap();
+    cache.members(&session, 200).await.unwrap();
+
+    let requests = transport.requests();
+    let paths: Vec<String> = requests.iter().map(Request::path_and_query).filter(|path| !path.starts_with("/auth")).collect();
+    assert_eq!(vec!["/groupList?sessionKey=SESSION", "/memberList?sessionKey=SESSION&target=100", "/memberList?sessionKey=SESSION&target=200", "/memberList?sessionKey=SESSION&target=200"], paths);
+}
+
+#[tokio::test]
+async fn bot_manager() {
+    let world = World::default().with_bot(MockBot::new(2, "Second Bot").with_friend(20, "Second Friend"));
+    let scenario = Scenario::with_world(world, 1).unwrap();
+    let auth_key = scenario.handle().world().auth_key.clone();
+    let mut manager = BotManager::new(scenario.connection());
+
+    // the replaced session is released
+    manager.add_bot(auth_key.as_str(), 1).await.unwrap();
+    manager.add_bot(auth_key.as_str(), 1).await.unwrap();
+    manager.add_bot(auth_key.as_str(), 2).await.unwrap();
+    assert_eq!(2, scenario.handle().world().sessions.len());
+
+    let mut bots = manager.bots();
+    bots.sort();
+    assert_eq!(vec![1, 2], bots);
+
+    let message = Message::new(vec!["Hello".into()]);
+
+    manager.pause(1);
+    assert!(!manager.is_online(1));
+
+    match manager.send_message(1, MessageChannel::Friend(10), &message).await {
+        Err(HttpError::BotPaused(1)) => {}
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    match manager.send_message(3, MessageChannel::Friend(10), &message).await {
+        Err(HttpError::StatusCode(error)) => assert_eq!(NO_SUCH_BOT, error.code()),
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    manager.resume(1);
+    manager.send_message(1, MessageChannel::Friend(10), &message).await.unwrap();
+    manager.send_message(2, MessageChannel::Friend(20), &message).await.unwrap();
+
+    let mut events = manager.listen(Duration::from_millis(20));
+    scenario.handle().friend_message(1, 10, vec!["To bot 1".into()]).unwrap();
+    scenario.handle().friend_message(2, 20, vec!["To bot 2".into()]).unwrap();
+    scenario.handle().push_event(1, json!({ "type": "BotOfflineEventActive", "qq": 1 }));
+
+    let mut received = Vec::new();
+    while received.len() < 3 {
+        let event = tokio::time::timeout(Duration::from_secs(1), events.recv()).await.unwrap().unwrap().unwrap();
+        received.push((event.bot, event.packet.name().to_string()));
+    }
+
+    received.sort();
+    assert_eq!(vec![(1, "BotLoginEvent".to_string()), (1, "MessageEvent".to_string()), (2, "MessageEvent".to_string())], received);
+    assert!(!manager.is_online(1));
+
+    // a removed bot is not listened anymore
+    assert!(manager.remove_bot(2).await.unwrap());
+    assert!(!manager.remove_bot(2).await.unwrap());
+    assert_eq!(vec![1], manager.bots());
+
+    tokio::time::delay_for(Duration::from_millis(50)).await;
+    scenario.handle().clear_records();
+    tokio::time::delay_for(Duration::from_millis(100)).await;
+    assert!(scenario.handle().requests().iter().all(|request| request.body["sessionKey"] != json!("MOCK_SESSION_3")));
+    assert!(!scenario.handle().requests().is_empty());
+
+    // an idle bot stops polling when the receiver is dropped
+    drop(events);
+    tokio::time::delay_for(Duration::from_millis(50)).await;
+    scenario.handle().clear_records();
+    tokio::time::delay_for(Duration::from_millis(100)).await;
+    assert!(scenario.handle().requests().is_empty());
+
+    assert!(manager.remove_bot(1).await.unwrap());
+    assert!(scenario.handle().world().sessions.is_empty());
+
+    // a session which fails verifying is released, and so are the sessions of a dropped manager
+    match manager.add_bot(auth_key.as_str(), 3).await {
+        Err(HttpError::StatusCode(error)) => assert_eq!(NO_SUCH_BOT, error.code()),
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    manager.add_bot(auth_key.as_str(), 2).await.unwrap();
+    drop(manager);
+    tokio::time::delay_for(Duration::from_millis(50)).await;
+    assert!(scenario.handle().world().sessions.is_empty());
+
+    let mut manager = BotManager::new(scenario.connection());
+    manager.add_bot(auth_key.as_str(), 1).await.unwrap();
+    manager.add_bot(auth_key.as_str(), 2).await.unwrap();
+    manager.shutdown().await.unwrap();
+    assert!(scenario.handle().world().sessions.is_empty());
+}
+
+#[tokio::test]
+async fn nudge() {
+    let scenario = Scenario::new().unwrap();
+    let session = scenario.session().await.unwrap();
+
+    session.send_nudge(10, 10, NudgeKind::Friend).await.unwrap();
+    session.send_nudge(11, 100, NudgeKind::Group).await.unwrap();
+
+    let bodies: Vec<_> = scenario.handle().requests().into_iter()
+        .filter(|request| request.path == "/sendNudge")
+        .map(|request| request.body)
+        .collect();
+
+    assert_eq!(2, bodies.len());
+    assert_eq!(json!({ "target": 10, "subject": 10, "kind": "Friend" }), json!({ "target": bodies[0]["target"], "subject": bodies[0]["subject"], "kind": bodies[0]["kind"] }));
+    assert_eq!(json!({ "target": 11, "subject": 100, "kind": "Group" }), json!({ "target": bodies[1]["target"], "subject": bodies[1]["subject"], "kind": bodies[1]["kind"] }));
+
+    match session.send_nudge(12, 100, NudgeKind::Group).await {
+        Err(HttpError::StatusCode(error)) => assert_eq!(NO_SUCH_TARGET, error.code()),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+fn assert_code<T: std::fmt::Debug>(code: mirai::Code, result: Result<T, HttpError>) {
+    match result {
+        Err(HttpError::StatusCode(error)) => assert_eq!(code, error.code()),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[tokio::test]
+async fn mock_world() {
+    let scenario = Scenario::new().unwrap();
+
+    assert_code(WRONG_AUTH_KEY, scenario.connection().auth("wrong key").await.map(drop));
+
+    let auth_key = scenario.handle().world().auth_key.clone();
+    let mut unbound = scenario.connection().auth(auth_key).await.unwrap();
+    assert_code(NO_SUCH_BOT, unbound.verify(999).await);
+    drop(unbound);
+
+    let session = scenario.session().await.unwrap();
+
+    assert_eq!(vec![10], session.friend_list().await.unwrap().iter().map(|friend| friend.id()).collect::<Vec<_>>());
+    assert_eq!(vec![100], session.group_list().await.unwrap().iter().map(|group| group.id()).collect::<Vec<_>>());
+
+    let mut members: Vec<_> = session.group_member_list(100).await.unwrap().iter().map(|member| member.id()).collect();
+    members.sort();
+    assert_eq!(vec![10, 11], members);
+
+    let id = session.send_message(MessageChannel::Group(100), &Message::new(vec!["Hello".into()])).await.unwrap();
+    assert_eq!("Hello", text_of(&scenario.handle().world().sent[&id].message_chain));
+
+    session.recall(id).await.unwrap();
+    assert!(scenario.handle().world().sent[&id].recalled);
+    assert_code(NO_SUCH_TARGET, session.recall(id).await);
+
+    assert_code(NO_SUCH_TARGET, session.send_message(MessageChannel::Group(999), &Message::new(vec!["Hello".into()])).await);
+    assert_code(MESSAGE_TOO_LONG, session.send_message(MessageChannel::Friend(10), &Message::new(vec!["x".repeat(5000).into()])).await);
+}
+
+#[tokio::test]
+async fn mock_handle() {
+    let scenario = Scenario::new().unwrap();
+    let session = scenario.session().await.unwrap();
+    let handle = scenario.handle();
+
+    let in_group = handle.group_message(1, 100, 11, vec!["In group".into()]).unwrap();
+    let in_private = handle.friend_message(1, 10, vec!["In private".into()]).unwrap();
+    handle.group_recall(1, 100, 11, in_group, Some(10)).unwrap();
+    handle.friend_recall(1, 10, in_private).unwrap();
+    handle.member_mute(1, 100, 11, 60).unwrap();
+
+    assert_eq!(60, handle.world().bots[&1].groups[&100].members[&11].mute_time_remaining);
+    assert_eq!(Err(NO_SUCH_TARGET), handle.group_message(1, 100, 999, vec!["Stranger".into()]));
+
+    // peeking doesn't take the events, and the newest events are newest first
+    let names = |packets: &[EventPacket]| packets.iter().map(|packet| packet.name().to_string()).collect::<Vec<_>>();
+    assert_eq!(vec!["MemberMuteEvent", "RecallEvent"], names(&session.peek_newest_message(2).await.unwrap()));
+
+    let packets = session.fetch_message(10).await.unwrap();
+    assert_eq!(vec!["MessageEvent", "MessageEvent", "RecallEvent", "RecallEvent", "MemberMuteEvent"], names(&packets));
+
+    match (&packets[0], &packets[1]) {
+        (EventPacket::MessageEvent(group), EventPacket::MessageEvent(friend)) => {
+            assert!(group.is_group() && friend.is_friend());
+            assert_eq!("In group", text_of(group.message_chain()));
+            assert_eq!("In private", text_of(friend.message_chain()));
+        }
+
+        packets => panic!("unexpected packets: {:?}", packets),
+    }
+
+    match (&packets[2], &packets[3]) {
+        (EventPacket::RecallEvent(RecallEvent::GroupRecallEvent { message_id: group, operator: Some(operator), .. }),
+            EventPacket::RecallEvent(RecallEvent::FriendRecallEvent { message_id: friend, .. })) => {
+            assert_eq!((in_group, in_private), (*group, *friend));
+            assert_eq!(10, operator.id());
+        }
+
+        packets => panic!("unexpected packets: {:?}", packets),
+    }
+
+    assert!(session.fetch_message(10).await.unwrap().is_empty());
+
+    session.send_message(MessageChannel::Friend(10), &Message::new(vec!["Reply".into()])).await.unwrap();
+
+    let sent = handle.sent_messages();
+    assert_eq!(1, sent.len());
+    assert_eq!(MessageChannel::Friend(10), sent[0].channel);
+    assert!(handle.requests().iter().any(|request| request.method == "POST" && request.path == "/sendFriendMessage"));
+
+    handle.clear_records();
+    assert!(handle.sent_messages().is_empty() && handle.requests().is_empty());
+}
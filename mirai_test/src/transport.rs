@@ -0,0 +1,103 @@
+//! An in-memory [`Transport`] which answers requests with scripted responses, no socket is needed.
+//!
+//! ```ignore
+//! let transport = FakeTransport::new();
+//! transport.respond("/about", About::response());
+//!
+//! let connection = transport.connection();
+//! let about = connection.about().await?;
+//!
+//! assert_eq!("/about", transport.requests()[0].path());
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use serde::Serialize;
+use serde_json::Value;
+
+use mirai::session::MiraiConnection;
+use mirai::transport::{Request, Transport, TransportFuture};
+use reqwest::Url;
+
+type Handler = Arc<dyn Fn(&Request) -> Value + Send + Sync>;
+
+#[derive(Default)]
+struct Inner {
+    handlers: HashMap<String, Handler>,
+    requests: Vec<Request>,
+}
+
+/// A [`Transport`] which answers requests by the handlers registered with their paths (without the query string).
+///
+/// It is cheap to clone, all the clones share the same handlers and recorded requests.
+/// Sending a request to a path which has no handler panics, the transport is still usable after that.
+#[derive(Clone, Default)]
+pub struct FakeTransport {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Debug for FakeTransport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let inner = self.inner.lock().unwrap();
+
+        f.debug_struct("FakeTransport")
+            .field("paths", &inner.handlers.keys().collect::<Vec<_>>())
+            .field("requests", &inner.requests)
+            .finish()
+    }
+}
+
+impl FakeTransport {
+    pub fn new() -> FakeTransport {
+        FakeTransport::default()
+    }
+
+    /// Create a connection which sends all the requests to this transport.
+    pub fn connection(&self) -> MiraiConnection {
+        MiraiConnection::with_transport("http://fake", self.clone()).unwrap()
+    }
+
+    /// Answer every request to [path] with [response].
+    pub fn respond<S: AsRef<str>, R: Serialize>(&self, path: S, response: R) -> &Self {
+        let response = serde_json::to_value(response).unwrap();
+
+        self.respond_with(path, move |_| response.clone())
+    }
+
+    /// Answer every request to [path] with the result of [handler].
+    pub fn respond_with<S, F>(&self, path: S, handler: F) -> &Self where
+        S: AsRef<str>,
+        F: Fn(&Request) -> Value + Send + Sync + 'static {
+        self.inner.lock().unwrap().handlers.insert(path.as_ref().to_string(), Arc::new(handler));
+        self
+    }
+
+    /// Return all the requests which were sent to this transport, in the order of sending.
+    pub fn requests(&self) -> Vec<Request> {
+        self.inner.lock().unwrap().requests.clone()
+    }
+
+    pub fn clear_requests(&self) {
+        self.inner.lock().unwrap().requests.clear();
+    }
+}
+
+impl Transport for FakeTransport {
+    fn send<'a>(&'a self, _: &'a Url, request: Request) -> TransportFuture<'a> {
+        Box::pin(async move {
+            // the handler is called without the lock, so a panic doesn't poison the transport
+            let handler = self.inner.lock().unwrap().handlers.get(request.path()).cloned();
+            let response = match handler {
+                Some(handler) => handler(&request),
+                None => panic!("FakeTransport: no response for {:?} {}", request.method(), request.path_and_query()),
+            };
+
+            self.inner.lock().unwrap().requests.push(request);
+
+            Ok(Bytes::from(serde_json::to_vec(&response).unwrap()))
+        })
+    }
+}